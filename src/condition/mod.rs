@@ -1,22 +1,114 @@
 //! Contains the [`Condition`] component.
 
 use alloc::sync::Arc;
-use core::fmt::Debug;
-use core::ops::RangeBounds;
+use core::fmt::{self, Debug, Display};
+use core::ops::{Bound, RangeBounds};
 
 use ustr::Ustr;
 
 use crate::prelude::*;
 
 pub mod relationship;
+pub mod system;
 
 /// A condition for an associated [`Operator`].
 /// If the condition is unfulfilled, the compound task containing the [`Operator`] may be pruned.
+///
+/// Conditions created by the shorthands like [`Condition::eq`] and combined with [`Condition::all`], [`Condition::any`] and [`Condition::not`]
+/// remember what they check, which can be inspected with [`Condition::expr`] and is printed by their [`Display`] implementation:
+///
+/// ```
+/// # use bevy_bae::prelude::*;
+/// let condition = Condition::any([
+///     Condition::in_range("health", ..30.0),
+///     Condition::not(Condition::always_false()),
+/// ]);
+/// assert_eq!(condition.to_string(), "(health < 30 || !false)");
+/// ```
 #[derive(Component, Clone, Reflect)]
 #[reflect(Component)]
 pub struct Condition {
     #[reflect(ignore, default = "Condition::true_pred")]
     predicate: Arc<dyn Fn(&mut Props) -> bool + Send + Sync + 'static>,
+    #[reflect(ignore)]
+    expr: ConditionExpr,
+}
+
+/// Describes what a [`Condition`] checks. See [`Condition::expr`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ConditionExpr {
+    /// A condition created from a closure with [`Condition::new`] or [`Condition::cmp`]. What it checks is not known.
+    #[default]
+    Custom,
+    /// A condition that always evaluates to this value. Created with [`Condition::always_true`] and [`Condition::always_false`].
+    Const(bool),
+    /// Compares a property with a value. Created with [`Condition::eq`], [`Condition::gt`] and the like.
+    Compare {
+        /// The name of the property.
+        name: Ustr,
+        /// How the property is compared.
+        op: CompareOp,
+        /// The value the property is compared with.
+        value: Value,
+    },
+    /// Checks whether a property is within a range. Created with [`Condition::in_range`].
+    InRange {
+        /// The name of the property.
+        name: Ustr,
+        /// The start of the range.
+        start: Bound<f32>,
+        /// The end of the range.
+        end: Bound<f32>,
+    },
+    /// Fulfilled if all of these conditions are fulfilled. Created with [`Condition::all`].
+    All(Vec<Condition>),
+    /// Fulfilled if any of these conditions is fulfilled. Created with [`Condition::any`].
+    Any(Vec<Condition>),
+    /// Fulfilled if this condition is not fulfilled. Created with [`Condition::not`].
+    Not(Box<Condition>),
+}
+
+/// The comparison used by [`ConditionExpr::Compare`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum CompareOp {
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+}
+
+impl CompareOp {
+    /// Compares the two values.
+    pub fn compare(self, a: Value, b: Value) -> bool {
+        match self {
+            Self::Eq => a == b,
+            Self::Ne => a != b,
+            Self::Gt => a > b,
+            Self::Ge => a >= b,
+            Self::Lt => a < b,
+            Self::Le => a <= b,
+        }
+    }
+
+    /// The operator as it would be written in Rust, e.g. `>=`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+        }
+    }
 }
 
 impl PartialEq for Condition {
@@ -29,8 +121,40 @@ impl Eq for Condition {}
 impl Condition {
     /// Creates a new condition with the given predicate.
     pub fn new(predicate: impl Fn(&mut Props) -> bool + Send + Sync + 'static) -> Self {
+        Self::with_expr(ConditionExpr::Custom, predicate)
+    }
+
+    fn with_expr(
+        expr: ConditionExpr,
+        predicate: impl Fn(&mut Props) -> bool + Send + Sync + 'static,
+    ) -> Self {
         Self {
             predicate: Arc::new(predicate),
+            expr,
+        }
+    }
+
+    /// Returns a description of what this condition checks.
+    pub fn expr(&self) -> &ConditionExpr {
+        &self.expr
+    }
+
+    /// Appends the names of the properties this condition reads to `props`, as far as they are known from its [`ConditionExpr`].
+    /// [`ConditionExpr::Custom`] conditions read unknown properties and add nothing.
+    pub fn read_props(&self, props: &mut Vec<Ustr>) {
+        match &self.expr {
+            ConditionExpr::Custom | ConditionExpr::Const(_) => {}
+            ConditionExpr::Compare { name, .. } | ConditionExpr::InRange { name, .. } => {
+                if !props.contains(name) {
+                    props.push(*name);
+                }
+            }
+            ConditionExpr::All(conditions) | ConditionExpr::Any(conditions) => {
+                for condition in conditions {
+                    condition.read_props(props);
+                }
+            }
+            ConditionExpr::Not(condition) => condition.read_props(props),
         }
     }
 
@@ -42,32 +166,32 @@ impl Condition {
 
     /// Shorthand for creating a condition for the concept of `props[name] == value`
     pub fn eq(name: impl Into<Ustr>, value: impl Into<Value>) -> Self {
-        Self::cmp(name, value, |a, b| a == b)
+        Self::compare(name, CompareOp::Eq, value)
     }
 
     /// Shorthand for creating a condition for the concept of `props[name] != value`
     pub fn ne(name: impl Into<Ustr>, value: impl Into<Value>) -> Self {
-        Self::cmp(name, value, |a, b| a != b)
+        Self::compare(name, CompareOp::Ne, value)
     }
 
     /// Shorthand for creating a condition for the concept of `props[name] > value`
     pub fn gt(name: impl Into<Ustr>, value: impl Into<Value>) -> Self {
-        Self::cmp(name, value, |a, b| a > b)
+        Self::compare(name, CompareOp::Gt, value)
     }
 
     /// Shorthand for creating a condition for the concept of `props[name] >= value`
     pub fn ge(name: impl Into<Ustr>, value: impl Into<Value>) -> Self {
-        Self::cmp(name, value, |a, b| a >= b)
+        Self::compare(name, CompareOp::Ge, value)
     }
 
     /// Shorthand for creating a condition for the concept of `props[name] < value`
     pub fn lt(name: impl Into<Ustr>, value: impl Into<Value>) -> Self {
-        Self::cmp(name, value, |a, b| a < b)
+        Self::compare(name, CompareOp::Lt, value)
     }
 
     /// Shorthand for creating a condition for the concept of `props[name] <= value`
     pub fn le(name: impl Into<Ustr>, value: impl Into<Value>) -> Self {
-        Self::cmp(name, value, |a, b| a <= b)
+        Self::compare(name, CompareOp::Le, value)
     }
 
     /// Shorthand for creating a condition for the concept of `range.contains(props[name])`
@@ -76,17 +200,53 @@ impl Condition {
         range: impl RangeBounds<f32> + Send + Sync + 'static,
     ) -> Self {
         let name = name.into();
-        Self::new(move |props| range.contains(props.get_mut::<f32>(name)))
+        let start = range.start_bound().cloned();
+        let end = range.end_bound().cloned();
+        Self::with_expr(ConditionExpr::InRange { name, start, end }, move |props| {
+            (start, end).contains(props.get_mut::<f32>(name))
+        })
     }
 
     /// Shorthand for creating a condition that always evaluates to true
     pub fn always_true() -> Self {
-        Self::new(|_| true)
+        Self::with_expr(ConditionExpr::Const(true), |_| true)
     }
 
     /// Shorthand for creating a condition that always evaluates to false
     pub fn always_false() -> Self {
-        Self::new(|_| false)
+        Self::with_expr(ConditionExpr::Const(false), |_| false)
+    }
+
+    /// Creates a condition that is fulfilled if all of the given conditions are fulfilled.
+    /// Stops evaluating at the first unfulfilled condition. An empty list is always fulfilled.
+    pub fn all(conditions: impl IntoIterator<Item = Condition>) -> Self {
+        let conditions = conditions.into_iter().collect::<Vec<_>>();
+        let children = conditions.clone();
+        Self::with_expr(ConditionExpr::All(conditions), move |props| {
+            children
+                .iter()
+                .all(|condition| condition.is_fullfilled(props))
+        })
+    }
+
+    /// Creates a condition that is fulfilled if any of the given conditions is fulfilled.
+    /// Stops evaluating at the first fulfilled condition. An empty list is never fulfilled.
+    pub fn any(conditions: impl IntoIterator<Item = Condition>) -> Self {
+        let conditions = conditions.into_iter().collect::<Vec<_>>();
+        let children = conditions.clone();
+        Self::with_expr(ConditionExpr::Any(conditions), move |props| {
+            children
+                .iter()
+                .any(|condition| condition.is_fullfilled(props))
+        })
+    }
+
+    /// Creates a condition that is fulfilled if the given condition is not fulfilled.
+    pub fn not(condition: Condition) -> Self {
+        let child = condition.clone();
+        Self::with_expr(ConditionExpr::Not(Box::new(condition)), move |props| {
+            !child.is_fullfilled(props)
+        })
     }
 
     /// Shortcut for creating a condition that compares a property with a value.
@@ -100,15 +260,72 @@ impl Condition {
         Self::new(move |p: &mut Props| predicate(*p.entry(name).or_default(), value))
     }
 
+    /// Shortcut for creating a condition that compares a property with a value using the given [`CompareOp`].
+    pub fn compare(name: impl Into<Ustr>, op: CompareOp, value: impl Into<Value>) -> Self {
+        let name = name.into();
+        let value = value.into();
+        Self::with_expr(ConditionExpr::Compare { name, op, value }, move |p| {
+            op.compare(*p.entry(name).or_default(), value)
+        })
+    }
+
     fn true_pred() -> Arc<dyn Fn(&mut Props) -> bool + Send + Sync + 'static> {
         Arc::new(|_| true)
     }
 }
 
 impl Debug for Condition {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Condition")
             .field("predicate", &"<callback>")
+            .field("expr", &self.expr)
             .finish()
     }
 }
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.expr, f)
+    }
+}
+
+impl Display for ConditionExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Custom => write!(f, "<callback>"),
+            Self::Const(value) => write!(f, "{value}"),
+            Self::Compare { name, op, value } => write!(f, "{name} {} {value:?}", op.as_str()),
+            Self::InRange { name, start, end } => {
+                match start {
+                    Bound::Included(start) => write!(f, "{start} <= ")?,
+                    Bound::Excluded(start) => write!(f, "{start} < ")?,
+                    Bound::Unbounded => {}
+                }
+                write!(f, "{name}")?;
+                match end {
+                    Bound::Included(end) => write!(f, " <= {end}"),
+                    Bound::Excluded(end) => write!(f, " < {end}"),
+                    Bound::Unbounded => Ok(()),
+                }
+            }
+            Self::All(conditions) if conditions.is_empty() => write!(f, "true"),
+            Self::Any(conditions) if conditions.is_empty() => write!(f, "false"),
+            Self::All(conditions) | Self::Any(conditions) => {
+                let separator = if matches!(self, Self::All(_)) {
+                    " && "
+                } else {
+                    " || "
+                };
+                write!(f, "(")?;
+                for (i, condition) in conditions.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "{separator}")?;
+                    }
+                    write!(f, "{condition}")?;
+                }
+                write!(f, ")")
+            }
+            Self::Not(condition) => write!(f, "!{condition}"),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Contains the [`SystemCondition`] component for conditions that need access to the ECS world.
+
+use core::fmt::Debug;
+
+use bevy_ecs::system::SystemId;
+use bevy_ecs::{lifecycle::HookContext, world::DeferredWorld};
+
+use crate::{plan::trace, prelude::*};
+
+/// The exact type of [`SystemId`] valid for [`SystemCondition`]s.
+pub type ConditionId = SystemId<In<ConditionInput>, bool>;
+
+/// A condition evaluated by a system instead of by looking at [`Props`], e.g. to check the distance to a target
+/// or the contents of an inventory without mirroring them into props first.
+/// Spawn it like a [`Condition`] with [`conditions!`]. An entity can hold both, in which case both must be fulfilled.
+///
+/// The system is evaluated against the live world both during planning and during execution.
+/// Since planning works on a copy of the [`Props`], the system cannot see the [`Effect`]s of earlier steps of the plan that is being built.
+/// If it depends on such effects, or is too expensive to run during planning, use [`SystemCondition::runtime_only`]
+/// to assume that it is fulfilled during planning and only check it right before the operator runs.
+/// A system that returns an error counts as unfulfilled.
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[component(on_insert = Self::on_insert_hook, on_replace = Self::on_replace_hook)]
+pub struct SystemCondition {
+    #[reflect(ignore)]
+    register_system: Option<Box<dyn FnOnce(&mut Commands) -> ConditionId + Send + Sync>>,
+    #[reflect(ignore)]
+    system_id: Option<ConditionId>,
+    /// Whether the condition is skipped during planning. See [`SystemCondition::runtime_only`].
+    pub runtime_only: bool,
+}
+
+impl Clone for SystemCondition {
+    fn clone(&self) -> Self {
+        Self {
+            register_system: None,
+            system_id: self.system_id,
+            runtime_only: self.runtime_only,
+        }
+    }
+}
+
+impl PartialEq for SystemCondition {
+    fn eq(&self, other: &Self) -> bool {
+        self.system_id == other.system_id && self.runtime_only == other.runtime_only
+    }
+}
+
+impl Eq for SystemCondition {}
+
+impl Debug for SystemCondition {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SystemCondition")
+            .field("system_id", &self.system_id)
+            .field("runtime_only", &self.runtime_only)
+            .finish()
+    }
+}
+
+impl SystemCondition {
+    /// Creates a new condition using the provided system. The system must take [`ConditionInput`] as input and return whether the condition is fulfilled.
+    pub fn new<S, M>(system: S) -> Self
+    where
+        S: IntoSystem<In<ConditionInput>, bool, M>,
+        S::System: Send + Sync + 'static,
+    {
+        let system = IntoSystem::into_system(system);
+        Self {
+            register_system: Some(Box::new(move |commands| commands.register_system(system))),
+            system_id: None,
+            runtime_only: false,
+        }
+    }
+
+    /// Assumes that the condition is fulfilled during planning, and only evaluates it during execution.
+    pub fn runtime_only(mut self) -> Self {
+        self.runtime_only = true;
+        self
+    }
+
+    /// Returns the [`SystemId`] of the registered condition one-shot system.
+    pub fn system_id(&self) -> ConditionId {
+        self.system_id.unwrap()
+    }
+
+    /// Evaluates the [`SystemCondition`] on the given entity, if any.
+    /// Returns `None` if the entity holds no [`SystemCondition`].
+    pub(crate) fn evaluate(
+        world: &mut World,
+        planner: Entity,
+        condition: Entity,
+        planning: bool,
+    ) -> Option<bool> {
+        let system_condition = world.get::<Self>(condition)?;
+        if planning && system_condition.runtime_only {
+            return Some(true);
+        }
+        let system_id = system_condition.system_id?;
+        let result = world.run_system_with(
+            system_id,
+            ConditionInput {
+                entity: planner,
+                condition,
+            },
+        );
+        world.flush();
+        Some(result.unwrap_or_else(|err| {
+            debug!(?planner, ?condition, %err, "condition system failed");
+            false
+        }))
+    }
+
+    fn on_insert_hook(mut world: DeferredWorld, context: HookContext) {
+        let Some(register_system) = world
+            .get_mut::<Self>(context.entity)
+            .and_then(|mut condition| condition.register_system.take())
+        else {
+            return;
+        };
+        let system_id = register_system(&mut world.commands());
+        world.get_mut::<Self>(context.entity).unwrap().system_id = Some(system_id);
+    }
+
+    fn on_replace_hook(mut world: DeferredWorld, context: HookContext) {
+        let Some(system_id) = world
+            .get::<Self>(context.entity)
+            .and_then(|condition| condition.system_id)
+        else {
+            return;
+        };
+        world.commands().unregister_system(system_id);
+    }
+}
+
+/// Inputs for a [`SystemCondition`].
+pub struct ConditionInput {
+    /// The entity up the hierarchy that holds the [`Plan`]. This is usually your entity of interest.
+    pub entity: Entity,
+    /// The entity that represents the condition itself.
+    pub condition: Entity,
+}
+
+/// Checks the [`Condition`] and [`SystemCondition`] on a condition entity during planning.
+/// Returns `None` if the entity holds neither, so that it can be ignored like before.
+pub(crate) fn check_planning_condition(
+    world: &mut World,
+    conditions: &mut QueryState<(Entity, &Condition)>,
+    world_state: &mut Props,
+    planner: Entity,
+    entity: Entity,
+) -> Option<bool> {
+    let fulfilled = conditions
+        .get(world, entity)
+        .ok()
+        .map(|(_, condition)| condition.is_fullfilled(world_state));
+    let fulfilled = if fulfilled == Some(false) {
+        fulfilled
+    } else {
+        SystemCondition::evaluate(world, planner, entity, true).or(fulfilled)
+    };
+    if let Some(fulfilled) = fulfilled {
+        trace::condition(world, entity, world_state, fulfilled);
+    }
+    fulfilled
+}
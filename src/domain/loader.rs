@@ -0,0 +1,92 @@
+//! Contains the [`DomainLoader`] for loading [`Domain`]s from `.domain.ron` and `.domain.json` files.
+
+use bevy_asset::{AssetLoader, LoadContext, io::Reader};
+use thiserror::Error;
+
+use crate::domain::Domain;
+
+/// Loads [`Domain`]s. Files ending in `.domain.ron` require the `ron` feature, and files ending in `.domain.json` require the `json` feature.
+/// Added automatically by [`DomainPlugin`](crate::domain::DomainPlugin).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DomainLoader;
+
+/// An error that occurred while loading a [`Domain`].
+#[derive(Debug, Error)]
+pub enum DomainLoaderError {
+    /// The file could not be read.
+    #[error("failed to read domain: {0}")]
+    Io(#[from] std::io::Error),
+    /// The file is not valid RON.
+    #[cfg(feature = "ron")]
+    #[error("failed to parse RON domain: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    /// The file is not valid JSON.
+    #[cfg(feature = "json")]
+    #[error("failed to parse JSON domain: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The file extension is neither of the supported ones.
+    #[error(
+        "unsupported domain file extension, expected one of {:?}",
+        DomainLoader::EXTENSIONS
+    )]
+    UnsupportedExtension,
+}
+
+impl DomainLoader {
+    #[cfg(all(feature = "ron", feature = "json"))]
+    const EXTENSIONS: &[&str] = &["domain.ron", "domain.json"];
+    #[cfg(all(feature = "ron", not(feature = "json")))]
+    const EXTENSIONS: &[&str] = &["domain.ron"];
+    #[cfg(all(feature = "json", not(feature = "ron")))]
+    const EXTENSIONS: &[&str] = &["domain.json"];
+}
+
+impl AssetLoader for DomainLoader {
+    type Asset = Domain;
+    type Settings = ();
+    type Error = DomainLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Domain, DomainLoaderError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let path = load_context.path().to_string_lossy();
+        #[cfg(feature = "ron")]
+        {
+            if path.ends_with(".ron") {
+                return Ok(Domain::from_ron(&bytes)?);
+            }
+        }
+        #[cfg(feature = "json")]
+        {
+            if path.ends_with(".json") {
+                return Ok(Domain::from_json(&bytes)?);
+            }
+        }
+        Err(DomainLoaderError::UnsupportedExtension)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        Self::EXTENSIONS
+    }
+}
+
+impl Domain {
+    /// Parses a domain from RON. Options can be written without `Some(...)`.
+    #[cfg(feature = "ron")]
+    pub fn from_ron(bytes: &[u8]) -> Result<Self, ron::error::SpannedError> {
+        ron::Options::default()
+            .with_default_extension(ron::extensions::Extensions::IMPLICIT_SOME)
+            .from_bytes(bytes)
+    }
+
+    /// Parses a domain from JSON.
+    #[cfg(feature = "json")]
+    pub fn from_json(bytes: &[u8]) -> Result<Self, serde_json::Error> {
+        serde_json::from_slice(bytes)
+    }
+}
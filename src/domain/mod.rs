@@ -0,0 +1,462 @@
+//! Contains the [`Domain`] asset for describing task hierarchies as data instead of code.
+//!
+//! A domain mirrors what you would otherwise write with [`tasks!`], [`conditions!`] and [`effects!`].
+//! [`Operator`]s are referred to by the name they were registered under with [`RegisterBehaviorExt::register_operator`],
+//! so the systems themselves stay in code while the structure of the behavior can be authored in files, e.g. in RON:
+//!
+//! ```ron
+//! (
+//!     name: "troll",
+//!     task: Select([
+//!         (
+//!             task: Operator("attack"),
+//!             conditions: [Eq("enemy_visible", true)],
+//!         ),
+//!         (
+//!             name: "patrol",
+//!             task: Sequence([
+//!                 (task: Operator("walk_to_next_waypoint")),
+//!                 (task: Operator("look_around"), effects: [Set("bored", true)]),
+//!             ]),
+//!         ),
+//!     ]),
+//! )
+//! ```
+//!
+//! Load such a file with the [`AssetServer`](bevy_asset::AssetServer) and insert it as a [`DomainHandle`] on your agent.
+//! Requires [`DomainPlugin`], and the `ron` or `json` features for loading `.domain.ron` or `.domain.json` files respectively.
+//!
+//! Conditions and effects can be simple comparisons and modifications of properties,
+//! refer to a [`Condition`] or [`Effect`] registered with [`RegisterBehaviorExt`] by name, e.g. `Named("can_see_player")`,
+//! or be written as [expressions](crate::expr), e.g. `Expr("health < 30 && !in_cover")` or `Expr("ammo -= 1")`.
+//!
+//! Domains can be hot reloaded: enable Bevy's `file_watcher` feature, and every edit to the file respawns
+//! the task hierarchy of all agents using it and makes them plan again, while keeping their [`Props`]. See [`DomainHandle`].
+
+use bevy_asset::prelude::*;
+use bevy_ecs::message::{MessageCursor, Messages};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    expr::ParseError,
+    plan::execution::abort_running_step,
+    prelude::*,
+    registry::{BehaviorRegistry, NamedCondition, NamedEffect, NamedOperator},
+};
+
+#[cfg(any(feature = "ron", feature = "json"))]
+pub mod loader;
+
+/// Adds support for [`Domain`] assets. Requires the [`AssetPlugin`] and [`BaePlugin`].
+pub struct DomainPlugin;
+
+impl Plugin for DomainPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Domain>();
+        #[cfg(any(feature = "ron", feature = "json"))]
+        app.register_asset_loader(loader::DomainLoader);
+        app.add_systems(
+            PreUpdate,
+            spawn_domains.after(bevy_asset::AssetTrackingSystems),
+        );
+    }
+}
+
+/// A serializable description of a task hierarchy, i.e. an HTN domain. See the [module docs](self) for an example.
+#[derive(Asset, TypePath, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Domain {
+    /// The task inserted on the entity holding the [`Plan`].
+    pub root: DomainTask,
+}
+
+/// A single task of a [`Domain`], along with its [`Condition`]s and [`Effect`]s.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DomainTask {
+    /// The [`Name`] of the task entity. Useful for logging.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// What kind of task this is.
+    pub task: DomainTaskKind,
+    /// Equivalent to [`conditions!`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<DomainCondition>,
+    /// Equivalent to [`effects!`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<DomainEffect>,
+}
+
+/// The kind of a [`DomainTask`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DomainTaskKind {
+    /// An [`Operator`] running the system registered under this name in the [`BehaviorRegistry`]. Spawned as a [`NamedOperator`].
+    Operator(String),
+    /// A [`Select`] with the given subtasks.
+    Select(Vec<DomainTask>),
+    /// A [`Sequence`] with the given subtasks.
+    Sequence(Vec<DomainTask>),
+}
+
+/// A [`Condition`] of a [`DomainTask`], comparing a property with a value or referring to a registered condition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DomainCondition {
+    /// The [`Condition`] registered under this name in the [`BehaviorRegistry`]. Spawned as a [`NamedCondition`].
+    Named(String),
+    /// Equivalent to [`Condition::eq`].
+    Eq(String, DomainValue),
+    /// Equivalent to [`Condition::ne`].
+    Ne(String, DomainValue),
+    /// Equivalent to [`Condition::gt`].
+    Gt(String, DomainValue),
+    /// Equivalent to [`Condition::ge`].
+    Ge(String, DomainValue),
+    /// Equivalent to [`Condition::lt`].
+    Lt(String, DomainValue),
+    /// Equivalent to [`Condition::le`].
+    Le(String, DomainValue),
+    /// An expression like `health < 30 && !in_cover`, parsed with [`Condition::parse`].
+    Expr(String),
+}
+
+impl DomainCondition {
+    fn spawn(&self, world: &mut World, entity: Entity) {
+        let condition = match self {
+            Self::Named(name) => {
+                world.spawn((ConditionOf(entity), NamedCondition::new(name.as_str())));
+                return;
+            }
+            Self::Eq(name, value) => Condition::eq(name.as_str(), value.clone()),
+            Self::Ne(name, value) => Condition::ne(name.as_str(), value.clone()),
+            Self::Gt(name, value) => Condition::gt(name.as_str(), value.clone()),
+            Self::Ge(name, value) => Condition::ge(name.as_str(), value.clone()),
+            Self::Lt(name, value) => Condition::lt(name.as_str(), value.clone()),
+            Self::Le(name, value) => Condition::le(name.as_str(), value.clone()),
+            // Validated before spawning
+            Self::Expr(expression) => Condition::parse(expression).unwrap(),
+        };
+        world.spawn((ConditionOf(entity), condition));
+    }
+}
+
+/// An [`Effect`] of a [`DomainTask`], modifying a property or referring to a registered effect.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DomainEffect {
+    /// The [`Effect`] registered under this name in the [`BehaviorRegistry`]. Spawned as a [`NamedEffect`].
+    Named(String),
+    /// Equivalent to [`Effect::set`].
+    Set(String, DomainValue),
+    /// Equivalent to [`Effect::toggle`].
+    Toggle(String),
+    /// Adds the value to the property.
+    Inc(String, DomainValue),
+    /// Subtracts the value from the property.
+    Dec(String, DomainValue),
+    /// Equivalent to [`Effect::mul`].
+    Mul(String, DomainValue),
+    /// Equivalent to [`Effect::div`].
+    Div(String, DomainValue),
+    /// An expression like `ammo -= 1`, parsed with [`Effect::parse`].
+    Expr(String),
+    /// The given effect with [`Effect::plan_only`] set.
+    PlanOnly(Box<DomainEffect>),
+}
+
+impl DomainEffect {
+    fn spawn(&self, world: &mut World, entity: Entity, plan_only: bool) {
+        let mut effect = match self {
+            Self::Named(name) => {
+                let mut named = NamedEffect::new(name.as_str());
+                named.plan_only = plan_only;
+                world.spawn((EffectOf(entity), named));
+                return;
+            }
+            Self::PlanOnly(effect) => {
+                effect.spawn(world, entity, true);
+                return;
+            }
+            Self::Set(name, value) => Effect::set(name.as_str(), value.clone()),
+            Self::Toggle(name) => Effect::toggle(name.as_str()),
+            Self::Inc(name, value) => Effect::mutate(name.as_str(), value.clone(), |a, b| *a += b),
+            Self::Dec(name, value) => Effect::mutate(name.as_str(), value.clone(), |a, b| *a -= b),
+            Self::Mul(name, value) => Effect::mutate(name.as_str(), value.clone(), |a, b| *a *= b),
+            Self::Div(name, value) => Effect::mutate(name.as_str(), value.clone(), |a, b| *a /= b),
+            // Validated before spawning
+            Self::Expr(expression) => Effect::parse(expression).unwrap(),
+        };
+        effect.plan_only = plan_only;
+        world.spawn((EffectOf(entity), effect));
+    }
+}
+
+/// A serializable [`Value`] of a property.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DomainValue {
+    /// A boolean.
+    Bool(bool),
+    /// A number. All numbers are stored as `f32`.
+    Number(f32),
+    /// A string.
+    String(String),
+}
+
+impl From<bool> for DomainValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f32> for DomainValue {
+    fn from(value: f32) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<String> for DomainValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for DomainValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<DomainValue> for Value {
+    fn from(value: DomainValue) -> Self {
+        match value {
+            DomainValue::Bool(value) => value.into(),
+            DomainValue::Number(value) => value.into(),
+            DomainValue::String(value) => value.as_str().into(),
+        }
+    }
+}
+
+/// An error that occurred while spawning a [`Domain`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DomainError {
+    /// An [`Operator`] referred to a name that was never registered in the [`BehaviorRegistry`].
+    #[error("no operator is registered under the name \"{0}\"")]
+    UnknownOperator(String),
+    /// A [`Condition`] referred to a name that was never registered in the [`BehaviorRegistry`].
+    #[error("no condition is registered under the name \"{0}\"")]
+    UnknownCondition(String),
+    /// An [`Effect`] referred to a name that was never registered in the [`BehaviorRegistry`].
+    #[error("no effect is registered under the name \"{0}\"")]
+    UnknownEffect(String),
+    /// A condition or effect expression could not be parsed.
+    #[error("invalid expression \"{expression}\": {error}")]
+    InvalidExpression {
+        /// The expression as written in the domain.
+        expression: String,
+        /// Why the expression is invalid.
+        error: ParseError,
+    },
+}
+
+impl DomainError {
+    fn invalid_expression(expression: &str, error: ParseError) -> Self {
+        Self::InvalidExpression {
+            expression: expression.to_string(),
+            error,
+        }
+    }
+}
+
+impl Domain {
+    /// Inserts the root task into the given entity and spawns all subtasks, conditions and effects below it.
+    /// Fails without changing the world if anything refers to a name that is not in the [`BehaviorRegistry`].
+    pub fn spawn(&self, world: &mut World, entity: Entity) -> Result<(), DomainError> {
+        self.validate(world)?;
+        self.root.insert(world, entity);
+        Ok(())
+    }
+
+    fn validate(&self, world: &mut World) -> Result<(), DomainError> {
+        self.root
+            .validate(&world.get_resource_or_init::<BehaviorRegistry>())
+    }
+}
+
+impl DomainTask {
+    fn validate(&self, registry: &BehaviorRegistry) -> Result<(), DomainError> {
+        for condition in &self.conditions {
+            match condition {
+                DomainCondition::Named(name) if registry.condition(name.as_str()).is_none() => {
+                    return Err(DomainError::UnknownCondition(name.clone()));
+                }
+                DomainCondition::Expr(expression) => {
+                    Condition::parse(expression)
+                        .map_err(|error| DomainError::invalid_expression(expression, error))?;
+                }
+                _ => {}
+            }
+        }
+        for mut effect in &self.effects {
+            while let DomainEffect::PlanOnly(inner) = effect {
+                effect = inner;
+            }
+            match effect {
+                DomainEffect::Named(name) if registry.effect(name.as_str()).is_none() => {
+                    return Err(DomainError::UnknownEffect(name.clone()));
+                }
+                DomainEffect::Expr(expression) => {
+                    Effect::parse(expression)
+                        .map_err(|error| DomainError::invalid_expression(expression, error))?;
+                }
+                _ => {}
+            }
+        }
+        match &self.task {
+            DomainTaskKind::Operator(name) if registry.operator_id(name.as_str()).is_none() => {
+                Err(DomainError::UnknownOperator(name.clone()))
+            }
+            DomainTaskKind::Operator(_) => Ok(()),
+            DomainTaskKind::Select(subtasks) | DomainTaskKind::Sequence(subtasks) => subtasks
+                .iter()
+                .try_for_each(|subtask| subtask.validate(registry)),
+        }
+    }
+
+    fn insert(&self, world: &mut World, entity: Entity) {
+        let mut entity_mut = world.entity_mut(entity);
+        if let Some(name) = &self.name {
+            entity_mut.insert(Name::new(name.clone()));
+        }
+        let subtasks = match &self.task {
+            DomainTaskKind::Operator(name) => {
+                entity_mut.insert(NamedOperator::new(name.as_str()));
+                None
+            }
+            DomainTaskKind::Select(subtasks) => {
+                entity_mut.insert(Select);
+                Some(subtasks)
+            }
+            DomainTaskKind::Sequence(subtasks) => {
+                entity_mut.insert(Sequence);
+                Some(subtasks)
+            }
+        };
+        for condition in &self.conditions {
+            condition.spawn(world, entity);
+        }
+        for effect in &self.effects {
+            effect.spawn(world, entity, false);
+        }
+        for subtask in subtasks.into_iter().flatten() {
+            let subtask_entity = world.spawn(TaskOf(entity)).id();
+            subtask.insert(world, subtask_entity);
+        }
+    }
+}
+
+/// Spawns the task hierarchy of a [`Domain`] on this entity once the asset is loaded, and then inserts a [`Plan`] and triggers [`UpdatePlan`].
+/// Requires [`DomainPlugin`].
+///
+/// Whenever the asset is modified, e.g. by hot reloading it through the [`AssetServer`](bevy_asset::AssetServer),
+/// or this component is replaced by one pointing to another asset, the running operator is aborted and
+/// the task hierarchy is despawned and spawned again from the new domain before triggering [`UpdatePlan`].
+/// [`Props`] are kept as they are. If the new domain fails to spawn, the old hierarchy is kept.
+/// A domain that fails to spawn, e.g. because it refers to behaviors that are not registered yet, is tried again every frame until it succeeds,
+/// which also applies to failed modifications. The error is logged once, and again only after the asset or the [`BehaviorRegistry`] changed.
+///
+/// Don't insert a [`Plan`] yourself, as that would try to plan before the tasks exist. You can however already insert [`Props`].
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq, Reflect, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct DomainHandle(pub Handle<Domain>);
+
+/// Marks that the [`Domain`] of a [`DomainHandle`] was spawned on this entity.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SpawnedDomain(pub(crate) AssetId<Domain>);
+
+fn spawn_domains(
+    world: &mut World,
+    mut domains: Local<QueryState<(Entity, &DomainHandle, Option<&SpawnedDomain>)>>,
+    mut asset_events: Local<MessageCursor<AssetEvent<Domain>>>,
+    mut modified: Local<Vec<AssetId<Domain>>>,
+    mut failed: Local<Vec<AssetId<Domain>>>,
+    mut scratch: Local<Vec<(Entity, AssetId<Domain>, Domain, bool)>>,
+) {
+    let count = modified.len();
+    modified.extend(
+        asset_events
+            .read(world.resource::<Messages<AssetEvent<Domain>>>())
+            .filter_map(|event| match event {
+                AssetEvent::Modified { id } => Some(*id),
+                _ => None,
+            }),
+    );
+    // Failures are only reported again once something changed that could fix them
+    if world.is_resource_changed::<BehaviorRegistry>() {
+        failed.clear();
+    }
+    let newly_modified = &modified[count..];
+    failed.retain(|id| !newly_modified.contains(id));
+    let assets = world.resource::<Assets<Domain>>();
+    scratch.extend(
+        domains
+            .iter(world)
+            .filter(|(_, handle, spawned)| {
+                spawned
+                    .is_none_or(|spawned| spawned.0 != handle.id() || modified.contains(&spawned.0))
+            })
+            .filter_map(|(entity, handle, spawned)| {
+                let domain = assets.get(&handle.0)?;
+                Some((entity, handle.id(), domain.clone(), spawned.is_some()))
+            }),
+    );
+    let mut failed_now = Vec::new();
+    for (entity, id, domain, respawn) in scratch.drain(..) {
+        // Not marked as spawned, so that it is tried again, e.g. once the missing behaviors are registered.
+        // A previously spawned domain keeps its marker and stays modified until it spawns, so a failed hot reload is tried again as well.
+        if let Err(err) = domain.validate(world) {
+            if failed.contains(&id) {
+                debug!(?entity, %err, "failed to spawn domain");
+            } else {
+                tracing::error!(?entity, %err, "failed to spawn domain");
+                failed.push(id);
+            }
+            failed_now.push(id);
+            continue;
+        }
+        if respawn {
+            despawn_domain(world, entity);
+        }
+        domain.root.insert(world, entity);
+        debug!(?entity, respawn, "spawned domain");
+        world
+            .entity_mut(entity)
+            .insert(SpawnedDomain(id))
+            .insert_if_new(Plan::new())
+            .trigger(UpdatePlan::new);
+    }
+    modified.retain(|id| failed_now.contains(id));
+}
+
+/// Aborts the running operator and despawns the task hierarchy spawned by a previous [`Domain`], keeping everything else on the entity.
+fn despawn_domain(world: &mut World, entity: Entity) {
+    abort_running_step(world, entity);
+    let mut entity_mut = world.entity_mut(entity);
+    if entity_mut.contains::<Plan>() {
+        entity_mut.insert(Plan::default());
+    }
+    entity_mut
+        .despawn_related::<Tasks>()
+        .despawn_related::<Conditions>()
+        .despawn_related::<Effects>()
+        .remove::<(
+            Tasks,
+            Conditions,
+            Effects,
+            Operator,
+            NamedOperator,
+            Select,
+            Sequence,
+        )>();
+    // The task validation and compound task observers clean up through commands,
+    // which need to be applied before the new root task is inserted.
+    world.flush();
+}
@@ -6,9 +6,11 @@ use core::fmt::Debug;
 use ustr::Ustr;
 
 pub mod relationship;
+pub mod system;
 
 /// An effect on the properties of the entity holding [`Plan`]. These effects are taken into account during planning,
 /// and applied automatically when the associated step of the plan succeeds.
+/// For effects on the world outside of the properties, add a [`SystemEffect`] to the same entity.
 #[derive(Component, Clone, Reflect)]
 #[reflect(Component)]
 pub struct Effect {
@@ -17,11 +19,20 @@ pub struct Effect {
     /// Whether the effect should be taken into account only during planning, but not applied for you.
     /// Default is `false`, i.e. all effects are applied when the associated step of the plan succeeds.
     pub plan_only: bool,
+    /// Whether the effect is verified after the operator succeeded instead of being applied. See [`Effect::verify`].
+    pub verify: bool,
+    #[reflect(ignore)]
+    expected: Option<Condition>,
+    #[reflect(ignore)]
+    custom: bool,
 }
 
 impl PartialEq for Effect {
     fn eq(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.effect, &other.effect) && self.plan_only == other.plan_only
+        Arc::ptr_eq(&self.effect, &other.effect)
+            && self.plan_only == other.plan_only
+            && self.verify == other.verify
+            && self.expected == other.expected
     }
 }
 
@@ -30,12 +41,33 @@ impl Eq for Effect {}
 impl Effect {
     /// Creates a new effect from the given function.
     pub fn new(fun: impl Fn(&mut Props) + Send + Sync + 'static) -> Self {
+        Self::with_custom(true, fun)
+    }
+
+    pub(crate) fn with_custom(
+        custom: bool,
+        fun: impl Fn(&mut Props) + Send + Sync + 'static,
+    ) -> Self {
         Self {
             effect: Arc::new(fun),
             plan_only: false,
+            verify: false,
+            expected: None,
+            custom,
         }
     }
 
+    /// Creates an effect that does nothing.
+    pub(crate) fn empty() -> Self {
+        Self::with_custom(false, |_| {})
+    }
+
+    /// Whether the effect was created from a closure, e.g. with [`Effect::new`] or [`Effect::mutate`], so that the properties it reads are unknown.
+    /// Effects created with [`Effect::set`] only write a value and don't read any properties.
+    pub fn is_custom(&self) -> bool {
+        self.custom
+    }
+
     /// Ensures that the effect is taken into account for planning, but not applied for you.
     /// This is useful for effects that come from the outside world, such as "did the monster find the player?".
     /// This is off by default, i.e. all effects are applied when the associated step of the plan succeeds.
@@ -44,6 +76,36 @@ impl Effect {
         self
     }
 
+    /// Treats the effect as the expected outcome of the operator rather than as something to apply for you.
+    /// Like with [`Effect::plan_only`], the effect is taken into account for planning, but not applied.
+    /// Instead, the properties are checked against the expected outcome of the effect when the associated step of the plan succeeds.
+    /// If they don't match, [`EffectMismatch`] is triggered and the plan fails, which leads to a replan.
+    ///
+    /// Only effects that describe their outcome can be verified, which are effects created with [`Effect::set`], or with [`Effect::parse`]
+    /// using only `=`. For other effects, use [`Effect::verify_with`].
+    pub fn verify(mut self) -> Self {
+        self.plan_only = true;
+        self.verify = true;
+        self
+    }
+
+    /// Like [`Effect::verify`], but uses the given condition to check whether the properties match the expected outcome.
+    /// This is needed for relative effects like [`Effect::mutate`], whose outcome depends on the properties before the operator ran.
+    pub fn verify_with(mut self, expected: Condition) -> Self {
+        self.expected = Some(expected);
+        self.verify()
+    }
+
+    /// The condition the properties are expected to fulfill after the effect took place, if known. Used by [`Effect::verify`].
+    pub fn expected(&self) -> Option<&Condition> {
+        self.expected.as_ref()
+    }
+
+    pub(crate) fn with_expected(mut self, expected: Option<Condition>) -> Self {
+        self.expected = expected;
+        self
+    }
+
     /// Applies the effect to the given properties.
     pub fn apply(&self, props: &mut Props) {
         (self.effect)(props);
@@ -53,7 +115,8 @@ impl Effect {
     pub fn set(name: impl Into<Ustr>, value: impl Into<Value>) -> Self {
         let name = name.into();
         let value = value.into();
-        Self::new(move |props| props.set(name, value))
+        Self::with_custom(false, move |props| props.set(name, value))
+            .with_expected(Some(Condition::eq(name, value)))
     }
 
     /// Shortcut for creating an effect that toggles a boolean property.
@@ -0,0 +1,142 @@
+//! Contains the [`SystemEffect`] component for effects that need access to the ECS world.
+
+use core::fmt::Debug;
+
+use bevy_ecs::system::SystemId;
+use bevy_ecs::{lifecycle::HookContext, world::DeferredWorld};
+
+use crate::prelude::*;
+
+/// The exact type of [`SystemId`] valid for [`SystemEffect`]s.
+pub type EffectId = SystemId<In<EffectInput>>;
+
+/// An effect that runs a system when it is applied during the execution of a [`Plan`], e.g. to write to components or trigger events.
+/// Spawn it like an [`Effect`] with [`effects!`].
+///
+/// A system cannot be run during planning, so planning only sees the [`Effect`] on the same entity,
+/// which is the projection of the system onto the [`Props`]. If there is none, a no-op [`Effect`] is inserted.
+/// When the step succeeds, the [`Effect`] is applied to the [`Props`] first, and then the system is run.
+/// If the [`Effect`] is [`Effect::plan_only`], the system is not run either.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_bae::prelude::*;
+/// # #[derive(Component)]
+/// # struct Weapon;
+/// # fn draw(_: In<OperatorInput>) -> OperatorStatus { OperatorStatus::Success }
+/// fn equip_weapon(input: In<EffectInput>, mut commands: Commands) {
+///     commands.entity(input.entity).insert(Weapon);
+/// }
+///
+/// # fn spawn(mut commands: Commands) {
+/// commands.spawn((
+///     Operator::new(draw),
+///     effects![(Effect::set("armed", true), SystemEffect::new(equip_weapon))],
+/// ));
+/// # }
+/// ```
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+#[component(on_insert = Self::on_insert_hook, on_replace = Self::on_replace_hook)]
+#[require(Effect = Effect::empty())]
+pub struct SystemEffect {
+    #[reflect(ignore)]
+    register_system: Option<Box<dyn FnOnce(&mut Commands) -> EffectId + Send + Sync>>,
+    #[reflect(ignore)]
+    system_id: Option<EffectId>,
+}
+
+impl Clone for SystemEffect {
+    fn clone(&self) -> Self {
+        Self {
+            register_system: None,
+            system_id: self.system_id,
+        }
+    }
+}
+
+impl PartialEq for SystemEffect {
+    fn eq(&self, other: &Self) -> bool {
+        self.system_id == other.system_id
+    }
+}
+
+impl Eq for SystemEffect {}
+
+impl Debug for SystemEffect {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SystemEffect")
+            .field("system_id", &self.system_id)
+            .finish()
+    }
+}
+
+impl SystemEffect {
+    /// Creates a new effect using the provided system. The system must take [`EffectInput`] as input.
+    pub fn new<S, M>(system: S) -> Self
+    where
+        S: IntoSystem<In<EffectInput>, (), M>,
+        S::System: Send + Sync + 'static,
+    {
+        let system = IntoSystem::into_system(system);
+        Self {
+            register_system: Some(Box::new(move |commands| commands.register_system(system))),
+            system_id: None,
+        }
+    }
+
+    /// Returns the [`SystemId`] of the registered effect one-shot system.
+    pub fn system_id(&self) -> EffectId {
+        self.system_id.unwrap()
+    }
+
+    /// Runs the [`SystemEffect`] on the given entity, if any.
+    pub(crate) fn run(world: &mut World, planner: Entity, effect: Entity) {
+        let Some(system_id) = world
+            .get::<Self>(effect)
+            .and_then(|effect| effect.system_id)
+        else {
+            return;
+        };
+        debug!(?planner, ?effect, "running effect system");
+        if let Err(err) = world.run_system_with(
+            system_id,
+            EffectInput {
+                entity: planner,
+                effect,
+            },
+        ) {
+            debug!(?planner, ?effect, %err, "effect system failed");
+        }
+        world.flush();
+    }
+
+    fn on_insert_hook(mut world: DeferredWorld, context: HookContext) {
+        let Some(register_system) = world
+            .get_mut::<Self>(context.entity)
+            .and_then(|mut effect| effect.register_system.take())
+        else {
+            return;
+        };
+        let system_id = register_system(&mut world.commands());
+        world.get_mut::<Self>(context.entity).unwrap().system_id = Some(system_id);
+    }
+
+    fn on_replace_hook(mut world: DeferredWorld, context: HookContext) {
+        let Some(system_id) = world
+            .get::<Self>(context.entity)
+            .and_then(|effect| effect.system_id)
+        else {
+            return;
+        };
+        world.commands().unregister_system(system_id);
+    }
+}
+
+/// Inputs for a [`SystemEffect`].
+pub struct EffectInput {
+    /// The entity up the hierarchy that holds the [`Plan`]. This is usually your entity of interest.
+    pub entity: Entity,
+    /// The entity that represents the effect itself.
+    pub effect: Entity,
+}
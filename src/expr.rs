@@ -0,0 +1,372 @@
+//! Contains a small expression language for writing [`Condition`]s and [`Effect`]s as strings,
+//! used by [`Condition::parse`] and [`Effect::parse`].
+//!
+//! Conditions are written like boolean expressions in Rust, e.g. `health < 30 && !in_cover`:
+//! - `prop == value`, `!=`, `<`, `<=`, `>` and `>=` compare a property on the left with a value on the right.
+//! - A property on its own, e.g. `in_cover`, is fulfilled if the property is `true`.
+//! - `&&`, `||`, `!` and parentheses combine conditions with the usual precedence,
+//!   resulting in [`Condition::all`], [`Condition::any`] and [`Condition::not`].
+//!
+//! Effects are written like assignments, e.g. `ammo -= 1`. The operators `=`, `+=`, `-=`, `*=` and `/=` are supported,
+//! and several assignments can be separated by `;`, e.g. `ammo -= 1; reloading = false`.
+//!
+//! Values are numbers like `30` or `-0.5`, `true`, `false`, or strings in double quotes like `"player"`.
+//! Property names consist of letters, digits, `_` and `.`, and must not start with a digit.
+
+use core::fmt::{self, Display};
+use core::iter::Peekable;
+use core::ops::Range;
+use core::str::CharIndices;
+
+use crate::{condition::CompareOp, prelude::*};
+
+/// An error that occurred while parsing an expression with [`Condition::parse`] or [`Effect::parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// What went wrong.
+    pub kind: ParseErrorKind,
+    /// The byte range of the expression where it went wrong.
+    pub span: Range<usize>,
+}
+
+/// What went wrong while parsing an expression. See [`ParseError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A character that is not part of the expression language.
+    UnexpectedCharacter(char),
+    /// A string without a closing `"`.
+    UnterminatedString,
+    /// A number that could not be parsed.
+    InvalidNumber,
+    /// Something other than what was expected.
+    UnexpectedToken {
+        /// A description of what was expected.
+        expected: &'static str,
+        /// The source text that was found instead.
+        found: String,
+    },
+    /// The expression ended while something was still expected.
+    UnexpectedEnd {
+        /// A description of what was expected.
+        expected: &'static str,
+    },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.kind, self.span.start, self.span.end)
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedCharacter(c) => write!(f, "unexpected character '{c}'"),
+            Self::UnterminatedString => write!(f, "unterminated string"),
+            Self::InvalidNumber => write!(f, "invalid number"),
+            Self::UnexpectedToken { expected, found } => {
+                write!(f, "expected {expected}, found \"{found}\"")
+            }
+            Self::UnexpectedEnd { expected } => {
+                write!(f, "expected {expected}, found end of expression")
+            }
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+impl Condition {
+    /// Parses a condition like `health < 30 && !in_cover`. See the [`expr`](crate::expr) module for the syntax.
+    pub fn parse(expression: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(expression)?;
+        let condition = parser.or()?;
+        parser.end()?;
+        Ok(condition)
+    }
+}
+
+impl Effect {
+    /// Parses an effect like `ammo -= 1`. See the [`expr`](crate::expr) module for the syntax.
+    pub fn parse(expression: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(expression)?;
+        let mut effects = vec![parser.assignment()?];
+        while parser.eat(";") {
+            if parser.peek().is_none() {
+                break;
+            }
+            effects.push(parser.assignment()?);
+        }
+        parser.end()?;
+        if effects.len() == 1 {
+            return Ok(effects.pop().unwrap());
+        }
+        let expected = effects
+            .iter()
+            .map(|effect| effect.expected().cloned())
+            .collect::<Option<Vec<_>>>()
+            .map(Condition::all);
+        let custom = effects.iter().any(Effect::is_custom);
+        Ok(Effect::with_custom(custom, move |props| {
+            for effect in &effects {
+                effect.apply(props);
+            }
+        })
+        .with_expected(expected))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Number(f32),
+    String(&'a str),
+    Bool(bool),
+    Symbol(&'static str),
+}
+
+/// Symbols ordered such that longer ones are matched first.
+const SYMBOLS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "+=", "-=", "*=", "/=", "<", ">", "!", "(", ")", "=", ";",
+    "-",
+];
+
+const COMPARISONS: &[(&str, CompareOp)] = &[
+    ("==", CompareOp::Eq),
+    ("!=", CompareOp::Ne),
+    ("<", CompareOp::Lt),
+    ("<=", CompareOp::Le),
+    (">", CompareOp::Gt),
+    (">=", CompareOp::Ge),
+];
+
+fn tokenize(source: &str) -> Result<Vec<(Token<'_>, Range<usize>)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c.is_alphabetic() || c == '_' {
+            let end = take_while(&mut chars, start, |c| {
+                c.is_alphanumeric() || c == '_' || c == '.'
+            });
+            let token = match &source[start..end] {
+                "true" => Token::Bool(true),
+                "false" => Token::Bool(false),
+                ident => Token::Ident(ident),
+            };
+            tokens.push((token, start..end));
+        } else if c.is_ascii_digit() || c == '.' {
+            let end = take_while(&mut chars, start, |c| c.is_ascii_digit() || c == '.');
+            let number = source[start..end].parse().map_err(|_| ParseError {
+                kind: ParseErrorKind::InvalidNumber,
+                span: start..end,
+            })?;
+            tokens.push((Token::Number(number), start..end));
+        } else if c == '"' {
+            chars.next();
+            let Some((end, _)) = chars.find(|&(_, c)| c == '"') else {
+                return Err(ParseError {
+                    kind: ParseErrorKind::UnterminatedString,
+                    span: start..source.len(),
+                });
+            };
+            tokens.push((Token::String(&source[start + 1..end]), start..end + 1));
+        } else if let Some(symbol) = SYMBOLS
+            .iter()
+            .find(|symbol| source[start..].starts_with(**symbol))
+        {
+            for _ in 0..symbol.len() {
+                chars.next();
+            }
+            tokens.push((Token::Symbol(*symbol), start..start + symbol.len()));
+        } else {
+            return Err(ParseError {
+                kind: ParseErrorKind::UnexpectedCharacter(c),
+                span: start..start + c.len_utf8(),
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+/// Consumes characters for as long as they match the predicate, and returns the end of the consumed range.
+fn take_while(
+    chars: &mut Peekable<CharIndices<'_>>,
+    start: usize,
+    predicate: fn(char) -> bool,
+) -> usize {
+    let mut end = start;
+    while let Some(&(i, c)) = chars.peek()
+        && predicate(c)
+    {
+        end = i + c.len_utf8();
+        chars.next();
+    }
+    end
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<(Token<'a>, Range<usize>)>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Result<Self, ParseError> {
+        Ok(Self {
+            source,
+            tokens: tokenize(source)?,
+            position: 0,
+        })
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.position).map(|(token, _)| *token)
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek();
+        self.position += 1;
+        token
+    }
+
+    /// Consumes the next token if it is the given symbol.
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if s == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    /// Creates an error for the token at the current position.
+    fn error(&self, expected: &'static str) -> ParseError {
+        match self.tokens.get(self.position) {
+            Some((_, span)) => ParseError {
+                kind: ParseErrorKind::UnexpectedToken {
+                    expected,
+                    found: self.source[span.clone()].to_string(),
+                },
+                span: span.clone(),
+            },
+            None => ParseError {
+                kind: ParseErrorKind::UnexpectedEnd { expected },
+                span: self.source.len()..self.source.len(),
+            },
+        }
+    }
+
+    fn end(&self) -> Result<(), ParseError> {
+        if self.peek().is_some() {
+            return Err(self.error("end of expression"));
+        }
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<Condition, ParseError> {
+        let mut conditions = vec![self.and()?];
+        while self.eat("||") {
+            conditions.push(self.and()?);
+        }
+        Ok(if conditions.len() == 1 {
+            conditions.pop().unwrap()
+        } else {
+            Condition::any(conditions)
+        })
+    }
+
+    fn and(&mut self) -> Result<Condition, ParseError> {
+        let mut conditions = vec![self.unary()?];
+        while self.eat("&&") {
+            conditions.push(self.unary()?);
+        }
+        Ok(if conditions.len() == 1 {
+            conditions.pop().unwrap()
+        } else {
+            Condition::all(conditions)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Condition, ParseError> {
+        if self.eat("!") {
+            return Ok(Condition::not(self.unary()?));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Condition, ParseError> {
+        const EXPECTED: &str = "a property, a boolean, '!' or '('";
+        match self.peek() {
+            Some(Token::Symbol("(")) => {
+                self.position += 1;
+                let condition = self.or()?;
+                if !self.eat(")") {
+                    return Err(self.error("')'"));
+                }
+                Ok(condition)
+            }
+            Some(Token::Bool(true)) => {
+                self.position += 1;
+                Ok(Condition::always_true())
+            }
+            Some(Token::Bool(false)) => {
+                self.position += 1;
+                Ok(Condition::always_false())
+            }
+            Some(Token::Ident(name)) => {
+                self.position += 1;
+                let comparison = COMPARISONS.iter().find(
+                    |(symbol, _)| matches!(self.peek(), Some(Token::Symbol(s)) if s == *symbol),
+                );
+                let Some(&(_, op)) = comparison else {
+                    return Ok(Condition::eq(name, true));
+                };
+                self.position += 1;
+                let value = self.value()?;
+                Ok(Condition::compare(name, op, value))
+            }
+            _ => Err(self.error(EXPECTED)),
+        }
+    }
+
+    fn assignment(&mut self) -> Result<Effect, ParseError> {
+        let Some(Token::Ident(name)) = self.peek() else {
+            return Err(self.error("a property"));
+        };
+        self.position += 1;
+        let Some(Token::Symbol(symbol @ ("=" | "+=" | "-=" | "*=" | "/="))) = self.peek() else {
+            return Err(self.error("'=', '+=', '-=', '*=' or '/='"));
+        };
+        self.position += 1;
+        let value = self.value()?;
+        Ok(match symbol {
+            "=" => Effect::set(name, value),
+            "+=" => Effect::mutate(name, value, |a, b| *a += b),
+            "-=" => Effect::mutate(name, value, |a, b| *a -= b),
+            "*=" => Effect::mutate(name, value, |a, b| *a *= b),
+            _ => Effect::mutate(name, value, |a, b| *a /= b),
+        })
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        const EXPECTED: &str = "a number, a boolean or a string";
+        let value = match self.peek() {
+            Some(Token::Number(number)) => number.into(),
+            Some(Token::Symbol("-")) => {
+                self.position += 1;
+                let Some(Token::Number(number)) = self.peek() else {
+                    return Err(self.error("a number"));
+                };
+                (-number).into()
+            }
+            Some(Token::Bool(value)) => value.into(),
+            Some(Token::String(value)) => value.into(),
+            _ => return Err(self.error(EXPECTED)),
+        };
+        self.next();
+        Ok(value)
+    }
+}
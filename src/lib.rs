@@ -2,29 +2,52 @@
 
 /// Everything you need to get started with `bevy_bae`
 pub mod prelude {
+    #[cfg(feature = "domain")]
+    pub use crate::domain::{Domain, DomainHandle, DomainPlugin};
     pub use crate::{
         BaePlugin, BaeSystems,
         bevy_mod_props::{self, PropCommandsExt, Props, PropsExt, PropsMutExt, Ustr, Value},
         condition::{
-            Condition,
+            CompareOp, Condition, ConditionExpr,
             relationship::{
                 ConditionOf, ConditionSpawner, ConditionSpawnerCommands, Conditions, conditions,
             },
+            system::{ConditionInput, SystemCondition},
         },
         effect::{
             Effect,
             relationship::{EffectOf, EffectSpawner, EffectSpawnerCommands, Effects, effects},
+            system::{EffectInput, SystemEffect},
+        },
+        plan::{
+            LogPlan, Plan, PlanFailure, PlanFailureReason,
+            cache::PlanCache,
+            event::{EffectMismatch, OperatorFinished, OperatorStarted, PlanCompleted, PlanFailed},
+            policy::ReplanPolicy,
+            queue::{PlanningBudget, PlanningQueue},
+            simulate::{SimulatePlanExt, SimulatedPlan},
+            trace::{DecompositionTrace, TraceOutcome, TracedCondition, TracedTask},
+            update::{ReplacePlan, UpdatePlan},
+            watch::{WatchProps, WatchSource},
+        },
+        registry::{
+            BehaviorRegistry, NamedCondition, NamedEffect, NamedOperator, RegisterBehaviorExt,
         },
-        plan::{LogPlan, Plan, update::UpdatePlan},
         task::{
             OperatorStatus,
             compound::{
                 CompoundTask,
+                include::Include,
+                parallel::{Parallel, ParallelPolicy},
+                random_select::{RandomSeed, RandomSelect, RandomWeight},
                 relationship::{TaskOf, TaskSpawner, TaskSpawnerCommands, Tasks, tasks},
+                repeat::{Repeat, RepeatMode},
                 select::Select,
-                sequence::Sequence,
+                sequence::{Backtrack, Sequence},
+                utility_select::{Utility, UtilitySelect},
             },
-            operator::{Operator, OperatorInput},
+            operator::{Operator, OperatorHook, OperatorInput},
+            shared::{DomainUsers, UsesDomain},
         },
     };
     pub(crate) use {
@@ -43,9 +66,13 @@ pub use bevy_mod_props::Ustr;
 
 use crate::{
     plan::{
+        cache::invalidate_plan_cache,
         execution::{execute_plan, update_empty_plans},
         log_plan,
+        policy::replan_on_policy,
+        queue::process_planning_queue,
         update::update_plan,
+        watch::{replan_on_watched_props, reset_watched_props},
     },
     prelude::*,
     task::{
@@ -55,9 +82,13 @@ use crate::{
 };
 
 pub mod condition;
+#[cfg(feature = "domain")]
+pub mod domain;
 pub mod effect;
+pub mod expr;
 mod name_ext;
 pub mod plan;
+pub mod registry;
 pub mod task;
 
 /// The plugin required to use `bevy_bae`. The schedule used can be configured with [`Self::new`], and the default is [`FixedUpdate`].
@@ -91,11 +122,28 @@ impl Plugin for BaePlugin {
             .add_observer(insert_bae_task_present_on_add::<Tasks>)
             .add_observer(remove_bae_task_present_on_remove::<Tasks>);
         app.add_compound_task::<Select>()
-            .add_compound_task::<Sequence>();
-        app.add_observer(update_plan).add_observer(log_plan);
+            .add_compound_task::<Sequence>()
+            .add_compound_task::<Parallel>()
+            .add_compound_task::<UtilitySelect>()
+            .add_compound_task::<RandomSelect>()
+            .add_compound_task::<Repeat>()
+            .add_compound_task::<Include>();
+        app.init_resource::<BehaviorRegistry>()
+            .init_resource::<PlanningQueue>();
+        app.add_observer(update_plan)
+            .add_observer(log_plan)
+            .add_observer(reset_watched_props)
+            .add_observer(invalidate_plan_cache::<Insert>)
+            .add_observer(invalidate_plan_cache::<Replace>);
         app.add_systems(
             self.schedule,
-            ((update_empty_plans, execute_plan)
+            ((
+                replan_on_watched_props,
+                replan_on_policy,
+                update_empty_plans,
+                process_planning_queue,
+                execute_plan,
+            )
                 .chain()
                 .in_set(BaeSystems::ExecutePlan),),
         );
@@ -0,0 +1,206 @@
+//! Plans many entities at once by decomposing their domains in parallel. Used by [`PlanningQueue::parallel`].
+//!
+//! Regular planning runs the decomposition systems of [`CompoundTask`]s with exclusive access to the [`World`], one planner at a time.
+//! Most domains however only need to read the task hierarchy and a copy of the [`Props`] of the planner,
+//! so their plans can be computed read-only on the [`ComputeTaskPool`] and then be written back in a single pass.
+//!
+//! The read-only path supports [`Operator`]s, [`Select`], [`Sequence`] including [`Backtrack`], and [`Condition`]s.
+//! Planners whose domain contains anything else, e.g. other [`CompoundTask`]s or [`SystemCondition`]s, are planned the regular way during the write pass.
+
+use bevy_ecs::entity_disabling::Disabled;
+use bevy_tasks::{ComputeTaskPool, ParallelSlice, TaskPool};
+
+use crate::{
+    plan::{
+        PlannedOperator,
+        update::{
+            PlanUpdate, apply_plan_update, previous_mtr, run_update_plan, single_operator_plan,
+            task_root,
+        },
+    },
+    prelude::*,
+    task::compound::{
+        DecomposeInput, DecomposeResult, Decomposer, Subtask, decompose_first_valid,
+        sequence::decompose_in_order,
+    },
+};
+
+/// Computes the plans of all given entities in parallel, and then applies them.
+pub(crate) fn plan_in_parallel(
+    world: &mut World,
+    effects: &mut QueryState<Entity, With<Effect>>,
+    planners: &[Entity],
+) {
+    let pool = ComputeTaskPool::get_or_init(TaskPool::default);
+    let read_only: &World = world;
+    let updates = planners
+        .par_splat_map(pool, None, |_, chunk| {
+            chunk
+                .iter()
+                .map(|&planner| plan_read_only(read_only, planner))
+                .collect::<Vec<_>>()
+        })
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    for (&planner, update) in planners.iter().zip(updates) {
+        // Observers of earlier planners may have despawned this one
+        world.flush();
+        if world.get_entity(planner).is_err() {
+            continue;
+        }
+        match update {
+            Some(update) => apply_plan_update(world, effects, planner, update),
+            None => {
+                debug!(
+                    ?planner,
+                    "domain cannot be planned read-only, falling back to regular planning"
+                );
+                run_update_plan(world, planner);
+            }
+        }
+    }
+}
+
+/// Computes the plan of the planner without mutating the world.
+/// Returns `None` if the domain contains something that can only be decomposed with exclusive world access, or the decomposition is traced.
+fn plan_read_only(world: &World, root: Entity) -> Option<PlanUpdate> {
+    if world.entity(root).contains::<DecompositionTrace>() {
+        return None;
+    }
+    let task_root = task_root(world, root);
+    let mut decomposer = ReadOnlyDecomposer {
+        world,
+        unsupported: false,
+    };
+    let mut world_state = world.get::<Props>(root)?.clone();
+    let mut initial_conditions = Vec::new();
+    for entity in world.get::<Conditions>(task_root).into_iter().flatten() {
+        match decomposer.check_condition(entity, &mut world_state) {
+            Some(true) => initial_conditions.push(entity),
+            Some(false) => return (!decomposer.unsupported).then_some(PlanUpdate::Clear),
+            None => {}
+        }
+    }
+
+    let task = Subtask::read(world, task_root)?;
+    if task.has_operator {
+        return Some(PlanUpdate::Replace(single_operator_plan(
+            task.entity,
+            initial_conditions,
+        )));
+    }
+    let result = decomposer.decompose_compound_task(DecomposeInput {
+        world_state,
+        plan: Plan::default(),
+        planner: root,
+        compound_task: task_root,
+        previous_mtr: previous_mtr(world, root),
+        conditions: initial_conditions,
+        skip: 0,
+    });
+    if decomposer.unsupported {
+        return None;
+    }
+    Some(PlanUpdate::from_result(world, root, result))
+}
+
+/// A [`Decomposer`] that only reads from the world, and thus can run for many planners in parallel.
+struct ReadOnlyDecomposer<'w> {
+    world: &'w World,
+    /// Set when something was encountered that can only be decomposed with exclusive world access.
+    /// The result of the decomposition is meaningless afterwards.
+    unsupported: bool,
+}
+
+impl ReadOnlyDecomposer<'_> {
+    /// Like [`check_planning_condition`](crate::condition::system::check_planning_condition), but without running [`SystemCondition`]s.
+    fn check_condition(&mut self, entity: Entity, world_state: &mut Props) -> Option<bool> {
+        let entity_ref = self.world.get_entity(entity).ok()?;
+        if entity_ref.contains::<SystemCondition>() {
+            self.unsupported = true;
+            return Some(false);
+        }
+        if entity_ref.contains::<Disabled>() {
+            return None;
+        }
+        entity_ref
+            .get::<Condition>()
+            .map(|condition| condition.is_fullfilled(world_state))
+    }
+
+    fn decompose_compound_task(&mut self, ctx: DecomposeInput) -> DecomposeResult {
+        let world = self.world;
+        let entity_ref = world.entity(ctx.compound_task);
+        let is_select = entity_ref.contains::<Select>();
+        if !is_select && !entity_ref.contains::<Sequence>() {
+            self.unsupported = true;
+            return DecomposeResult::Failure;
+        }
+        let Some(tasks) = entity_ref.get::<Tasks>() else {
+            return DecomposeResult::Failure;
+        };
+        let subtasks = tasks
+            .iter()
+            .filter_map(|entity| Subtask::read(world, entity))
+            .collect::<Vec<_>>();
+        if is_select {
+            decompose_first_valid(self, ctx, &subtasks)
+        } else {
+            let backtrack = entity_ref.contains::<Backtrack>();
+            decompose_in_order(self, ctx, &subtasks, backtrack)
+        }
+    }
+}
+
+impl Decomposer for ReadOnlyDecomposer<'_> {
+    fn decompose_subtask(&mut self, subtask: &Subtask, mut ctx: DecomposeInput) -> DecomposeResult {
+        if self.unsupported {
+            return DecomposeResult::Failure;
+        }
+        for entity in subtask.conditions.iter().flatten() {
+            match self.check_condition(entity, &mut ctx.world_state) {
+                Some(true) => ctx.conditions.push(entity),
+                Some(false) => return DecomposeResult::Failure,
+                None => {}
+            }
+        }
+        let (mut plan, mut world_state) = if subtask.has_operator {
+            if ctx.skip > 0 {
+                return DecomposeResult::Failure;
+            }
+            ctx.plan.push_back(PlannedOperator {
+                entity: subtask.entity,
+                effects: vec![],
+                conditions: ctx.conditions,
+                parallel: None,
+                started: false,
+            });
+            (ctx.plan, ctx.world_state)
+        } else {
+            match self.decompose_compound_task(DecomposeInput {
+                compound_task: subtask.entity,
+                ..ctx
+            }) {
+                DecomposeResult::Success { plan, world_state } => (plan, world_state),
+                result => return result,
+            }
+        };
+        if plan.is_empty() {
+            return DecomposeResult::Failure;
+        }
+        for entity in subtask.effects.iter().flatten() {
+            let Ok(entity_ref) = self.world.get_entity(entity) else {
+                continue;
+            };
+            if let Some(effect) = entity_ref.get::<Effect>()
+                && !entity_ref.contains::<Disabled>()
+            {
+                effect.apply(&mut world_state);
+                plan.back_mut().unwrap().effects.push(entity);
+            }
+        }
+        DecomposeResult::Success { plan, world_state }
+    }
+}
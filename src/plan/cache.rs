@@ -0,0 +1,299 @@
+//! Contains the [`PlanCache`] resource for reusing the results of previous decompositions.
+
+use alloc::collections::VecDeque;
+use bevy_ecs::{entity::EntityHashMap, entity_disabling::Disabled};
+
+use crate::{
+    plan::update::PlanUpdate,
+    prelude::*,
+    task::compound::{DecomposeResult, TypeErasedCompoundTask},
+};
+
+/// Remembers the plans found for a domain, so that planning again with the same relevant [`Props`] skips the decomposition entirely.
+///
+/// Agents often replan to the very same plan over and over, e.g. when using [`ReplanPolicy::every`] or [`WatchProps`].
+/// When this resource exists, the result of decomposing a domain is stored under the values of the properties read by the [`Condition`]s of the domain.
+/// The next time any planner using that domain is planned with the same values for these properties, the stored result is used instead.
+/// Since planners sharing a domain through [`UsesDomain`] share their cache entries as well, this is especially effective for crowds of agents.
+///
+/// Only domains whose plans are fully determined by these properties are cached. Domains containing any of the following are always decomposed:
+/// - [`Condition`]s created from closures, e.g. with [`Condition::new`], since the properties they read are unknown
+/// - [`Effect`]s created from closures, e.g. with [`Effect::new`] or [`Effect::inc`], for the same reason. See [`Effect::is_custom`].
+/// - [`SystemCondition`]s
+/// - [`UtilitySelect`], [`RandomSelect`] and custom [`CompoundTask`]s
+///
+/// Domains decomposed in parallel because of [`PlanningQueue::parallel`] don't use the cache.
+///
+/// The whole cache is cleared whenever a task hierarchy changes, i.e. when tasks, conditions or effects are inserted, removed, replaced or disabled.
+/// Mutating them in place is not detected, so call [`PlanCache::clear`] after doing so.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_bae::prelude::*;
+/// # let mut app = App::new();
+/// app.add_plugins(BaePlugin::default())
+///     .init_resource::<PlanCache>();
+/// ```
+#[derive(Resource, Debug)]
+pub struct PlanCache {
+    /// The maximum number of results stored per domain. When exceeded, the least recently used result is dropped.
+    /// Default is [`PlanCache::DEFAULT_CAPACITY`].
+    pub capacity: usize,
+    domains: EntityHashMap<CachedDomain>,
+    hits: u64,
+    misses: u64,
+}
+
+impl Default for PlanCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+#[derive(Debug, Default)]
+struct CachedDomain {
+    /// The properties the plans of the domain depend on, or `None` if the domain cannot be cached.
+    props: Option<Vec<Ustr>>,
+    /// The cached results, least recently used first.
+    results: VecDeque<CachedResult>,
+}
+
+#[derive(Debug)]
+struct CachedResult {
+    key: Vec<Value>,
+    /// The decomposed plan, or `None` if the decomposition failed.
+    plan: Option<Plan>,
+}
+
+impl PlanCache {
+    /// The default value of [`PlanCache::capacity`].
+    pub const DEFAULT_CAPACITY: usize = 32;
+
+    /// Creates a new empty cache storing at most `capacity` results per domain.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            domains: EntityHashMap::default(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the number of stored results across all domains.
+    pub fn len(&self) -> usize {
+        self.domains
+            .values()
+            .map(|domain| domain.results.len())
+            .sum()
+    }
+
+    /// Returns whether no results are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns how often planning was skipped because a stored result was used.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns how often a cacheable domain had to be decomposed because no stored result matched.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Forgets all stored results.
+    pub fn clear(&mut self) {
+        self.domains.clear();
+    }
+
+    fn store(&mut self, task_root: Entity, key: Vec<Value>, plan: Option<Plan>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let domain = self.domains.entry(task_root).or_default();
+        domain.results.retain(|result| result.key != key);
+        if domain.results.len() >= self.capacity {
+            domain.results.pop_front();
+        }
+        domain.results.push_back(CachedResult { key, plan });
+    }
+
+    fn get(
+        &mut self,
+        task_root: Entity,
+        key: &[Value],
+        skip_failures: bool,
+    ) -> Option<Option<Plan>> {
+        let domain = self.domains.get_mut(&task_root)?;
+        let Some(index) = domain
+            .results
+            .iter()
+            .position(|result| result.key == key && !(skip_failures && result.plan.is_none()))
+        else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        let result = domain.results.remove(index)?;
+        let plan = result.plan.clone();
+        domain.results.push_back(result);
+        Some(plan)
+    }
+}
+
+/// Returns the values of the properties the plans of the domain depend on, or `None` if there is no [`PlanCache`] or the domain cannot be cached.
+pub(crate) fn cache_key(
+    world: &mut World,
+    task_root: Entity,
+    world_state: &mut Props,
+) -> Option<Vec<Value>> {
+    if !world.contains_resource::<PlanCache>() {
+        return None;
+    }
+    world.resource_scope(|world, mut cache: Mut<PlanCache>| {
+        let domain = cache
+            .domains
+            .entry(task_root)
+            .or_insert_with(|| CachedDomain {
+                props: domain_props(world, task_root),
+                results: VecDeque::new(),
+            });
+        let props = domain.props.as_ref()?;
+        Some(
+            props
+                .iter()
+                .map(|&name| *world_state.entry(name).or_default())
+                .collect(),
+        )
+    })
+}
+
+/// Looks up the stored result for the key and turns it into a [`PlanUpdate`] for the planner.
+pub(crate) fn cached_update(
+    world: &mut World,
+    root: Entity,
+    task_root: Entity,
+    key: &[Value],
+) -> Option<PlanUpdate> {
+    // A failure found without a running plan may be a rejection with one, which keeps the running plan instead of aborting it
+    let running = world.get::<Plan>(root).is_some_and(|plan| !plan.is_empty());
+    let plan = world
+        .resource_mut::<PlanCache>()
+        .get(task_root, key, running)?;
+    debug!(planner=?root, domain=?task_root, "reusing cached plan");
+    let Some(plan) = plan else {
+        return Some(PlanUpdate::Replace(Plan::default()));
+    };
+    // The decomposition would have been rejected for having a lower priority than the running plan
+    if !plan.mtr.is_empty()
+        && world
+            .get::<Plan>(root)
+            .is_some_and(|previous| plan.mtr > previous.mtr)
+    {
+        return Some(PlanUpdate::Keep);
+    }
+    Some(PlanUpdate::from_plan(world, root, plan))
+}
+
+/// Stores the result of a decomposition, replacing any earlier result for the same key.
+/// Rejections depend on the running plan, so they are not stored, and stored failures are only used while no plan is running.
+pub(crate) fn store_result(
+    world: &mut World,
+    task_root: Entity,
+    key: Vec<Value>,
+    result: &DecomposeResult,
+) {
+    let plan = match result {
+        DecomposeResult::Success { plan, .. } => Some(plan.clone()),
+        DecomposeResult::Failure => None,
+        DecomposeResult::Rejection => return,
+    };
+    if let Some(mut cache) = world.get_resource_mut::<PlanCache>() {
+        cache.store(task_root, key, plan);
+    }
+}
+
+/// Collects the properties read by the domain, or returns `None` if its plans depend on anything else.
+fn domain_props(world: &World, task_root: Entity) -> Option<Vec<Ustr>> {
+    let mut props = Vec::new();
+    let mut stack = vec![task_root];
+    let mut visited = Vec::new();
+    while let Some(task) = stack.pop() {
+        if visited.contains(&task) {
+            continue;
+        }
+        visited.push(task);
+        let task_ref = world.get_entity(task).ok()?;
+        for condition in task_ref.get::<Conditions>().into_iter().flatten() {
+            let condition_ref = world.get_entity(condition).ok()?;
+            if condition_ref.contains::<SystemCondition>() {
+                return None;
+            }
+            if let Some(condition) = condition_ref.get::<Condition>() {
+                if !is_known(condition.expr()) {
+                    return None;
+                }
+                condition.read_props(&mut props);
+            }
+        }
+        for effect in task_ref.get::<Effects>().into_iter().flatten() {
+            if world.get::<Effect>(effect).is_none_or(Effect::is_custom) {
+                return None;
+            }
+        }
+        if task_ref.contains::<TypeErasedCompoundTask>()
+            && !task_ref.contains::<Select>()
+            && !task_ref.contains::<Sequence>()
+            && !task_ref.contains::<Parallel>()
+            && !task_ref.contains::<Repeat>()
+            && !task_ref.contains::<Include>()
+        {
+            return None;
+        }
+        stack.extend(task_ref.get::<Tasks>().into_iter().flatten());
+        stack.extend(task_ref.get::<Include>().map(|include| include.0));
+    }
+    Some(props)
+}
+
+/// Returns whether the properties read by the condition are fully described by its expression.
+fn is_known(expr: &ConditionExpr) -> bool {
+    match expr {
+        ConditionExpr::Custom => false,
+        ConditionExpr::Const(_) | ConditionExpr::Compare { .. } | ConditionExpr::InRange { .. } => {
+            true
+        }
+        ConditionExpr::All(conditions) | ConditionExpr::Any(conditions) => conditions
+            .iter()
+            .all(|condition| is_known(condition.expr())),
+        ConditionExpr::Not(condition) => is_known(condition.expr()),
+    }
+}
+
+/// The components making up task hierarchies. Inserting or replacing any of them invalidates the [`PlanCache`].
+type HierarchyComponents = (
+    TaskOf,
+    ConditionOf,
+    EffectOf,
+    Condition,
+    SystemCondition,
+    Effect,
+    Operator,
+    TypeErasedCompoundTask,
+    Backtrack,
+    Repeat,
+    Parallel,
+    Include,
+    Disabled,
+);
+
+/// Clears the [`PlanCache`] when a task hierarchy changes. Registered for both [`Insert`] and [`Replace`], which also covers removals.
+pub(crate) fn invalidate_plan_cache<E: EntityEvent>(
+    _: On<E, HierarchyComponents>,
+    cache: Option<ResMut<PlanCache>>,
+) {
+    if let Some(mut cache) = cache {
+        cache.clear();
+    }
+}
@@ -0,0 +1,73 @@
+//! Contains the [`EntityEvent`]s triggered automatically during the execution of a [`Plan`].
+//!
+//! All events target the entity holding the [`Plan`], so you can observe them either globally or on a specific agent:
+//!
+//! ```
+//! # use bevy::prelude::*;
+//! # use bevy_bae::prelude::*;
+//! # let mut world = World::new();
+//! world.add_observer(|failed: On<PlanFailed>| {
+//!     info!("plan of {} failed: {:?}", failed.entity, failed.failure.reason);
+//! });
+//! ```
+//!
+//! See also [`ReplacePlan`], which is triggered whenever a new plan is computed.
+
+use crate::prelude::*;
+
+/// Triggered when the last step of a [`Plan`] completed successfully.
+/// The now empty plan will be recomputed in the next fixed frame.
+#[derive(EntityEvent, Debug, Clone, PartialEq, Eq)]
+pub struct PlanCompleted {
+    /// The entity holding the [`Plan`].
+    #[event_target]
+    pub entity: Entity,
+}
+
+/// Triggered when a step of a [`Plan`] failed, which aborts the whole plan.
+/// The plan will be recomputed in the next fixed frame.
+/// At this point, the [`PlanFailure`] component of the entity already holds the same failure.
+#[derive(EntityEvent, Debug, Clone, PartialEq, Eq)]
+pub struct PlanFailed {
+    /// The entity holding the [`Plan`].
+    #[event_target]
+    pub entity: Entity,
+    /// Why and when the plan failed.
+    pub failure: PlanFailure,
+}
+
+/// Triggered right before an [`Operator`] runs for the first time, after its [`OperatorHook::Enter`] was run.
+#[derive(EntityEvent, Debug, Clone, PartialEq, Eq)]
+pub struct OperatorStarted {
+    /// The entity holding the [`Plan`].
+    #[event_target]
+    pub entity: Entity,
+    /// The entity holding the [`Operator`].
+    pub operator: Entity,
+}
+
+/// Triggered when a started [`Operator`] will no longer run, after its [`OperatorHook::Exit`] or [`OperatorHook::Abort`] was run.
+#[derive(EntityEvent, Debug, Clone, PartialEq, Eq)]
+pub struct OperatorFinished {
+    /// The entity holding the [`Plan`].
+    #[event_target]
+    pub entity: Entity,
+    /// The entity holding the [`Operator`].
+    pub operator: Entity,
+    /// [`OperatorStatus::Success`] if the operator completed, and [`OperatorStatus::Failure`] if it failed or was aborted.
+    pub status: OperatorStatus,
+}
+
+/// Triggered when the properties don't match an [`Effect::verify`] effect after its [`Operator`] succeeded.
+/// Afterwards, the operator is aborted like a failed one, i.e. [`OperatorFinished`] is triggered with [`OperatorStatus::Failure`],
+/// and the plan fails with [`PlanFailureReason::EffectMismatch`] and is recomputed in the next fixed frame.
+#[derive(EntityEvent, Debug, Clone, PartialEq, Eq)]
+pub struct EffectMismatch {
+    /// The entity holding the [`Plan`].
+    #[event_target]
+    pub entity: Entity,
+    /// The entity holding the [`Operator`], or the [`Parallel`] task.
+    pub operator: Entity,
+    /// The entity holding the [`Effect`] that was not fulfilled.
+    pub effect: Entity,
+}
@@ -1,4 +1,11 @@
-use crate::{plan::PlannedOperator, prelude::*};
+use crate::{
+    plan::{
+        PlannedOperator, PlannedParallel,
+        watch::{acknowledge_watched_props, unchanged_watched_props},
+    },
+    prelude::*,
+    task::{compound::parallel::ParallelPolicy, operator::OperatorHookId},
+};
 
 pub(crate) fn update_empty_plans(
     mut plans: Query<(Entity, NameOrEntity, &Plan)>,
@@ -11,12 +18,13 @@ pub(crate) fn update_empty_plans(
         }
     }
 }
+
 pub(crate) fn execute_plan(
     world: &mut World,
     mut plans: Local<QueryState<(NameOrEntity, &mut Plan)>>,
-    mut conditions: Local<QueryState<(NameOrEntity, &Condition)>>,
-    mut operators: Local<QueryState<(NameOrEntity, &Operator)>>,
-    mut effects: Local<QueryState<(NameOrEntity, &Effect)>>,
+    mut conditions: Local<QueryState<(NameOrEntity, &'static Condition)>>,
+    mut operators: Local<QueryState<(NameOrEntity, &'static Operator)>>,
+    mut effects: Local<QueryState<(NameOrEntity, &'static Effect)>>,
     mut plans_scratch: Local<Vec<(Entity, Option<Name>, PlannedOperator)>>,
     mut condition_scratch: Local<Vec<(Entity, Option<Name>, Condition)>>,
     mut effects_scratch: Local<Vec<(Entity, Option<Name>, Effect)>>,
@@ -26,71 +34,31 @@ pub(crate) fn execute_plan(
             Some((name.entity, name.name.cloned(), plan.front()?.clone()))
         }),
     );
-    for (plan_entity, plan_name, planned_operator) in plans_scratch.drain(..) {
+    let mut executor = PlanExecutor {
+        conditions: &mut conditions,
+        operators: &mut operators,
+        effects: &mut effects,
+        condition_scratch: &mut condition_scratch,
+        effects_scratch: &mut effects_scratch,
+    };
+    for (plan_entity, plan_name, mut planned_operator) in plans_scratch.drain(..) {
         if !world.entity_mut(plan_entity).contains::<Props>() {
             world.entity_mut(plan_entity).insert(Props::default());
         }
-        debug!(?plan_entity, ?plan_name, "checking conditions");
-        let mut all_conditions_met = true;
-        {
-            condition_scratch.extend(
-                conditions
-                    .iter_many(world, planned_operator.conditions.iter())
-                    .map(|(name, condition)| (name.entity, name.name.cloned(), condition.clone())),
-            );
-            let mut entity_mut = world.entity_mut(plan_entity);
-            let mut props = entity_mut.get_mut::<Props>().unwrap();
-            for (condition_entity, condition_name, condition) in condition_scratch.drain(..) {
-                if condition.is_fullfilled(&mut props) {
-                    debug!(
-                        ?plan_entity,
-                        ?plan_name,
-                        ?condition_entity,
-                        ?condition_name,
-                        "satisfied condition"
-                    );
-                } else {
-                    debug!(
-                        ?plan_entity,
-                        ?plan_name,
-                        ?condition_entity,
-                        ?condition_name,
-                        "encountered unsatisfied condition, aborting plan"
-                    );
-                    all_conditions_met = false;
-                    break;
-                }
-            }
-        }
-        let result: Result<OperatorStatus, _> = if all_conditions_met {
-            let input = OperatorInput {
-                entity: plan_entity,
-                operator: planned_operator.entity,
-            };
-            if let Ok((op_name, operator)) = operators.get(world, planned_operator.entity) {
-                debug!(
-                    ?plan_entity,
-                    ?plan_name,
-                    operator_entity=?op_name.entity,
-                    operator_name=?op_name.name,
-                    "running operator"
-                );
-                let result = world.run_system_with(operator.system_id(), input);
-                world.flush();
-                result
-            } else {
-                debug!(
-                    operator_entity=?planned_operator.entity,
-                    "failed to find operator"
-                );
-                Ok(OperatorStatus::Failure)
-            }
-        } else {
-            Ok(OperatorStatus::Failure)
-        };
+        // Operators and their hooks may replace the plan while running
+        let plan_changed = world
+            .entity(plan_entity)
+            .get_change_ticks::<Plan>()
+            .map(|ticks| ticks.changed);
+        let result = executor.run_step(
+            world,
+            plan_entity,
+            plan_name.as_ref(),
+            &mut planned_operator,
+        );
 
         let (force_replan, plan_entity_alive) = match result {
-            Ok(OperatorStatus::Success) => {
+            StepResult::Success => {
                 debug!(
                     ?plan_entity,
                     ?plan_name,
@@ -99,55 +67,63 @@ pub(crate) fn execute_plan(
                 match world.get_entity_mut(plan_entity) {
                     Ok(mut entity_mut) => {
                         let step = entity_mut.get_mut::<Plan>().unwrap().pop_front().unwrap();
-
-                        effects_scratch.extend(effects.iter_many(world, step.effects.iter()).map(
-                            |(name, effect)| (name.entity, name.name.cloned(), effect.clone()),
-                        ));
-                        let mut entity = world.entity_mut(plan_entity);
-                        let mut props = entity.get_mut::<Props>().unwrap();
-                        for (effect_entity, effect_name, effect) in effects_scratch.drain(..) {
-                            if effect.plan_only {
-                                debug!(
-                                    ?plan_entity,
-                                    ?plan_name,
-                                    ?effect_entity,
-                                    ?effect_name,
-                                    "skipping effect as it's plan_only"
-                                );
-                            } else {
-                                debug!(
-                                    ?plan_entity,
-                                    ?plan_name,
-                                    ?effect_entity,
-                                    ?effect_name,
-                                    "applying effect"
-                                );
-                                effect.apply(&mut props);
-                            }
+                        executor.apply_effects(
+                            world,
+                            plan_entity,
+                            plan_name.as_ref(),
+                            &step.effects,
+                        );
+                        if world
+                            .get::<Plan>(plan_entity)
+                            .is_some_and(|plan| plan.is_empty())
+                        {
+                            debug!(?plan_entity, ?plan_name, "plan completed");
+                            world.trigger(PlanCompleted {
+                                entity: plan_entity,
+                            });
                         }
-
                         (false, true)
                     }
                     _ => (false, false),
                 }
             }
-            Ok(OperatorStatus::Ongoing) => {
+            StepResult::Ongoing => {
                 debug!(?plan_entity, ?plan_name, "operator ongoing");
+                // Store the progress made by the branches of a parallel step, unless the plan was replaced in the meantime
+                if let Ok(mut entity_mut) = world.get_entity_mut(plan_entity)
+                    && entity_mut
+                        .get_change_ticks::<Plan>()
+                        .map(|ticks| ticks.changed)
+                        == plan_changed
+                    && let Some(mut plan) = entity_mut.get_mut::<Plan>()
+                    && let Some(front) = plan.front_mut()
+                    && front.entity == planned_operator.entity
+                {
+                    *front = planned_operator;
+                }
                 // Even if the current plan is empty, we still want to continue the execution of the last step!
                 continue;
             }
-            Ok(OperatorStatus::Failure) => {
-                debug!(?plan_entity, ?plan_name, "operator failed, aborting plan");
-                (true, world.get_entity(plan_entity).is_ok())
-            }
-            Err(err) => {
+            StepResult::Failure(reason) => {
                 debug!(
                     ?plan_entity,
                     ?plan_name,
-                    ?err,
-                    "operator system failed, aborting plan"
+                    ?reason,
+                    "step failed, aborting plan"
                 );
-                (true, world.get_entity(plan_entity).is_ok())
+                let plan_entity_alive = world.get_entity(plan_entity).is_ok();
+                if plan_entity_alive {
+                    let failure = PlanFailure {
+                        reason,
+                        tick: world.read_change_tick(),
+                    };
+                    world.entity_mut(plan_entity).insert(failure.clone());
+                    world.trigger(PlanFailed {
+                        entity: plan_entity,
+                        failure,
+                    });
+                }
+                (true, plan_entity_alive)
             }
         };
         if plan_entity_alive {
@@ -166,3 +142,450 @@ pub(crate) fn execute_plan(
         }
     }
 }
+
+/// The outcome of running a single step of a [`Plan`] once.
+enum StepResult {
+    Success,
+    Ongoing,
+    Failure(PlanFailureReason),
+}
+
+/// Runs the steps of [`Plan`]s. Bundles the state used by [`execute_plan`] so that it can be reused for the branches of [`PlannedParallel`] steps.
+struct PlanExecutor<'a> {
+    conditions: &'a mut QueryState<(NameOrEntity, &'static Condition)>,
+    operators: &'a mut QueryState<(NameOrEntity, &'static Operator)>,
+    effects: &'a mut QueryState<(NameOrEntity, &'static Effect)>,
+    condition_scratch: &'a mut Vec<(Entity, Option<Name>, Condition)>,
+    effects_scratch: &'a mut Vec<(Entity, Option<Name>, Effect)>,
+}
+
+impl PlanExecutor<'_> {
+    /// Checks the conditions of the step and runs it once. Does not advance the plan.
+    fn run_step(
+        &mut self,
+        world: &mut World,
+        plan_entity: Entity,
+        plan_name: Option<&Name>,
+        planned_operator: &mut PlannedOperator,
+    ) -> StepResult {
+        debug!(?plan_entity, ?plan_name, "checking conditions");
+        if let Some(condition) =
+            self.unmet_condition(world, plan_entity, plan_name, &planned_operator.conditions)
+        {
+            abort_step(world, plan_entity, planned_operator);
+            return StepResult::Failure(PlanFailureReason::UnmetCondition {
+                condition,
+                operator: planned_operator.entity,
+            });
+        }
+        if let Some(parallel) = &mut planned_operator.parallel {
+            debug!(
+                ?plan_entity,
+                ?plan_name,
+                parallel_entity=?planned_operator.entity,
+                "running parallel branches"
+            );
+            let result = self.run_parallel(world, plan_entity, plan_name, parallel);
+            return self.verify_effects(world, plan_entity, plan_name, planned_operator, result);
+        }
+
+        let input = OperatorInput {
+            entity: plan_entity,
+            operator: planned_operator.entity,
+        };
+        if let Ok((op_name, operator)) = self.operators.get(world, planned_operator.entity) {
+            debug!(
+                ?plan_entity,
+                ?plan_name,
+                operator_entity=?op_name.entity,
+                operator_name=?op_name.name,
+                "running operator"
+            );
+            let system_id = operator.system_id();
+            if !planned_operator.started {
+                planned_operator.started = true;
+                // Stored before running the hook, so that the step is aborted if the plan gets replaced from here on
+                if let Some(mut plan) = world.get_mut::<Plan>(plan_entity)
+                    && let Some(front) = plan.bypass_change_detection().front_mut()
+                {
+                    mark_started(front, planned_operator.entity);
+                }
+                run_hook(
+                    world,
+                    plan_entity,
+                    planned_operator.entity,
+                    OperatorHook::Enter,
+                );
+            }
+            let result = world.run_system_with(system_id, input);
+            world.flush();
+            let operator = planned_operator.entity;
+            match result {
+                Ok(OperatorStatus::Success) => {
+                    // Only counts as finished once the effects are verified, otherwise it is aborted like a failed operator
+                    let result = self.verify_effects(
+                        world,
+                        plan_entity,
+                        plan_name,
+                        planned_operator,
+                        StepResult::Success,
+                    );
+                    let hook = match result {
+                        StepResult::Success => OperatorHook::Exit,
+                        _ => OperatorHook::Abort,
+                    };
+                    run_hook(world, plan_entity, operator, hook);
+                    result
+                }
+                Ok(OperatorStatus::Ongoing) => StepResult::Ongoing,
+                Ok(OperatorStatus::Failure) => {
+                    run_hook(world, plan_entity, operator, OperatorHook::Abort);
+                    StepResult::Failure(PlanFailureReason::OperatorFailed { operator })
+                }
+                Err(err) => {
+                    run_hook(world, plan_entity, operator, OperatorHook::Abort);
+                    StepResult::Failure(PlanFailureReason::SystemError {
+                        operator,
+                        error: err.to_string(),
+                    })
+                }
+            }
+        } else {
+            debug!(
+                operator_entity=?planned_operator.entity,
+                "failed to find operator"
+            );
+            StepResult::Failure(PlanFailureReason::MissingOperator {
+                operator: planned_operator.entity,
+            })
+        }
+    }
+
+    /// Runs the front step of every running branch once, and advances the branches whose step succeeded.
+    fn run_parallel(
+        &mut self,
+        world: &mut World,
+        plan_entity: Entity,
+        plan_name: Option<&Name>,
+        parallel: &mut PlannedParallel,
+    ) -> StepResult {
+        let mut failure = None;
+        for branch in parallel
+            .branches
+            .iter_mut()
+            .filter(|branch| branch.status == OperatorStatus::Ongoing)
+        {
+            let Some(step) = branch.operators_left.front_mut() else {
+                branch.status = OperatorStatus::Success;
+                continue;
+            };
+            match self.run_step(world, plan_entity, plan_name, step) {
+                StepResult::Success => {
+                    let step = branch.operators_left.pop_front().unwrap();
+                    self.apply_effects(world, plan_entity, plan_name, &step.effects);
+                    if branch.operators_left.is_empty() {
+                        debug!(?plan_entity, ?plan_name, "branch completed successfully");
+                        branch.status = OperatorStatus::Success;
+                    }
+                }
+                StepResult::Ongoing => {}
+                StepResult::Failure(reason) => {
+                    debug!(?plan_entity, ?plan_name, ?reason, "branch failed");
+                    branch.status = OperatorStatus::Failure;
+                    failure = Some(reason);
+                }
+            }
+            if parallel.policy == ParallelPolicy::FirstFinished
+                && branch.status != OperatorStatus::Ongoing
+            {
+                break;
+            }
+        }
+        let status = parallel.status();
+        if status != OperatorStatus::Ongoing {
+            // The branches that are still running will never complete
+            for branch in parallel
+                .branches
+                .iter()
+                .filter(|branch| branch.status == OperatorStatus::Ongoing)
+            {
+                if let Some(step) = branch.operators_left.front() {
+                    abort_step(world, plan_entity, step);
+                }
+            }
+        }
+        match (status, failure) {
+            (OperatorStatus::Success, _) => StepResult::Success,
+            // A parallel step can only fail in the same run in which one of its branches failed
+            (OperatorStatus::Failure, Some(reason)) => StepResult::Failure(reason),
+            _ => StepResult::Ongoing,
+        }
+    }
+
+    /// Turns a successful step into a failure if the props don't match one of its [`Effect::verify`] effects.
+    fn verify_effects(
+        &mut self,
+        world: &mut World,
+        plan_entity: Entity,
+        plan_name: Option<&Name>,
+        planned_operator: &PlannedOperator,
+        result: StepResult,
+    ) -> StepResult {
+        if !matches!(result, StepResult::Success) {
+            return result;
+        }
+        self.effects_scratch.extend(
+            self.effects
+                .iter_many(world, planned_operator.effects.iter())
+                .filter(|(_, effect)| effect.verify)
+                .map(|(name, effect)| (name.entity, name.name.cloned(), effect.clone())),
+        );
+        let Some(mut props) = world
+            .get_entity_mut(plan_entity)
+            .ok()
+            .and_then(|entity_mut| entity_mut.into_mut::<Props>())
+        else {
+            self.effects_scratch.clear();
+            return result;
+        };
+        let mut mismatch = None;
+        for (effect_entity, effect_name, effect) in self.effects_scratch.drain(..) {
+            let Some(expected) = effect.expected() else {
+                debug!(
+                    ?plan_entity,
+                    ?plan_name,
+                    ?effect_entity,
+                    ?effect_name,
+                    "skipping verification of effect without an expected outcome"
+                );
+                continue;
+            };
+            if mismatch.is_none() && !expected.is_fullfilled(&mut props) {
+                debug!(
+                    ?plan_entity,
+                    ?plan_name,
+                    ?effect_entity,
+                    ?effect_name,
+                    "props don't match the expected outcome of the effect, aborting plan"
+                );
+                mismatch = Some(effect_entity);
+            }
+        }
+        let Some(effect) = mismatch else {
+            return result;
+        };
+        world.trigger(EffectMismatch {
+            entity: plan_entity,
+            operator: planned_operator.entity,
+            effect,
+        });
+        StepResult::Failure(PlanFailureReason::EffectMismatch { effect })
+    }
+
+    /// Returns the first of the given conditions that is not fulfilled, if any.
+    fn unmet_condition(
+        &mut self,
+        world: &mut World,
+        plan_entity: Entity,
+        plan_name: Option<&Name>,
+        conditions: &[Entity],
+    ) -> Option<Entity> {
+        self.condition_scratch.extend(
+            self.conditions
+                .iter_many(world, conditions.iter())
+                .map(|(name, condition)| (name.entity, name.name.cloned(), condition.clone())),
+        );
+        let Some(mut props) = world
+            .get_entity_mut(plan_entity)
+            .ok()
+            .and_then(|entity_mut| entity_mut.into_mut::<Props>())
+        else {
+            self.condition_scratch.clear();
+            // Without props, no condition can be fulfilled
+            return conditions.first().copied();
+        };
+        for (condition_entity, condition_name, condition) in self.condition_scratch.drain(..) {
+            if condition.is_fullfilled(&mut props) {
+                debug!(
+                    ?plan_entity,
+                    ?plan_name,
+                    ?condition_entity,
+                    ?condition_name,
+                    "satisfied condition"
+                );
+            } else {
+                debug!(
+                    ?plan_entity,
+                    ?plan_name,
+                    ?condition_entity,
+                    ?condition_name,
+                    "encountered unsatisfied condition, aborting plan"
+                );
+                return Some(condition_entity);
+            }
+        }
+        for &condition_entity in conditions {
+            if SystemCondition::evaluate(world, plan_entity, condition_entity, false) == Some(false)
+            {
+                debug!(
+                    ?plan_entity,
+                    ?plan_name,
+                    ?condition_entity,
+                    "encountered unsatisfied condition system, aborting plan"
+                );
+                return Some(condition_entity);
+            }
+        }
+        None
+    }
+
+    fn apply_effects(
+        &mut self,
+        world: &mut World,
+        plan_entity: Entity,
+        plan_name: Option<&Name>,
+        effects: &[Entity],
+    ) {
+        let unchanged_watched_props = unchanged_watched_props(world, plan_entity);
+        self.effects_scratch.extend(
+            self.effects
+                .iter_many(world, effects.iter())
+                .map(|(name, effect)| (name.entity, name.name.cloned(), effect.clone())),
+        );
+        let Some(mut props) = world
+            .get_entity_mut(plan_entity)
+            .ok()
+            .and_then(|entity_mut| entity_mut.into_mut::<Props>())
+        else {
+            self.effects_scratch.clear();
+            return;
+        };
+        for (effect_entity, effect_name, effect) in self.effects_scratch.drain(..) {
+            if effect.plan_only {
+                debug!(
+                    ?plan_entity,
+                    ?plan_name,
+                    ?effect_entity,
+                    ?effect_name,
+                    "skipping effect as it's plan_only"
+                );
+            } else {
+                debug!(
+                    ?plan_entity,
+                    ?plan_name,
+                    ?effect_entity,
+                    ?effect_name,
+                    "applying effect"
+                );
+                effect.apply(&mut props);
+            }
+        }
+        for &effect_entity in effects {
+            if world
+                .get::<Effect>(effect_entity)
+                .is_some_and(|effect| !effect.plan_only)
+            {
+                SystemEffect::run(world, plan_entity, effect_entity);
+            }
+        }
+        acknowledge_watched_props(world, plan_entity, &unchanged_watched_props);
+    }
+}
+
+/// Runs the [`OperatorHook::Abort`] of the front step of the [`Plan`] of the given entity, if it was started.
+/// Call this before replacing a running plan.
+pub(crate) fn abort_running_step(world: &mut World, plan_entity: Entity) {
+    let Some(step) = world
+        .get::<Plan>(plan_entity)
+        .and_then(|plan| plan.front().cloned())
+    else {
+        return;
+    };
+    abort_step(world, plan_entity, &step);
+}
+
+/// Marks the step running the operator as started, searching the running branches if the step is a parallel step.
+fn mark_started(step: &mut PlannedOperator, operator: Entity) -> bool {
+    let Some(parallel) = &mut step.parallel else {
+        if step.entity == operator {
+            step.started = true;
+        }
+        return step.entity == operator;
+    };
+    parallel
+        .branches
+        .iter_mut()
+        .filter(|branch| branch.status == OperatorStatus::Ongoing)
+        .filter_map(|branch| branch.operators_left.front_mut())
+        .any(|step| mark_started(step, operator))
+}
+
+/// Runs the [`OperatorHook::Abort`] of the given step if it was started, or of the started steps of its running branches if it is a parallel step.
+fn abort_step(world: &mut World, plan_entity: Entity, step: &PlannedOperator) {
+    if let Some(parallel) = &step.parallel {
+        for branch in parallel
+            .branches
+            .iter()
+            .filter(|branch| branch.status == OperatorStatus::Ongoing)
+        {
+            if let Some(step) = branch.operators_left.front() {
+                abort_step(world, plan_entity, step);
+            }
+        }
+    } else if step.started {
+        run_hook(world, plan_entity, step.entity, OperatorHook::Abort);
+    }
+}
+
+/// Runs the given hook of the operator, if it has one, and triggers the matching [`OperatorStarted`] or [`OperatorFinished`] event.
+fn run_hook(world: &mut World, plan_entity: Entity, operator: Entity, hook: OperatorHook) {
+    if let Some(system_id) = world
+        .get::<Operator>(operator)
+        .and_then(|operator| operator.hook(hook))
+    {
+        run_hook_system(world, plan_entity, operator, hook, system_id);
+    }
+    let entity = plan_entity;
+    match hook {
+        OperatorHook::Enter => world.trigger(OperatorStarted { entity, operator }),
+        OperatorHook::Exit => world.trigger(OperatorFinished {
+            entity,
+            operator,
+            status: OperatorStatus::Success,
+        }),
+        OperatorHook::Abort => world.trigger(OperatorFinished {
+            entity,
+            operator,
+            status: OperatorStatus::Failure,
+        }),
+    }
+}
+
+fn run_hook_system(
+    world: &mut World,
+    plan_entity: Entity,
+    operator: Entity,
+    hook: OperatorHook,
+    system_id: OperatorHookId,
+) {
+    debug!(
+        ?plan_entity,
+        operator_entity=?operator,
+        ?hook,
+        "running operator hook"
+    );
+    let input = OperatorInput {
+        entity: plan_entity,
+        operator,
+    };
+    if let Err(err) = world.run_system_with(system_id, input) {
+        debug!(
+            ?plan_entity,
+            operator_entity=?operator,
+            ?hook,
+            ?err,
+            "operator hook failed"
+        );
+    }
+    world.flush();
+}
@@ -1,25 +1,34 @@
 //! Contains the [`Plan`] component and types for operating on it.
 
 use alloc::collections::VecDeque;
-use bevy_ecs::{entity_disabling::Disabled, query::QueryEntityError};
+use bevy_ecs::{component::Tick, entity_disabling::Disabled, query::QueryEntityError};
 
-use crate::{plan::mtr::Mtr, prelude::*};
+use crate::{plan::mtr::Mtr, prelude::*, task::compound::parallel::ParallelPolicy};
 
+pub(crate) mod batch;
+pub mod cache;
+pub mod event;
 pub(crate) mod execution;
 pub mod mtr;
+pub mod policy;
+pub mod queue;
+pub mod simulate;
+pub mod trace;
 pub mod update;
+pub mod watch;
 
 /// A full plan of operators to execute. If this is empty, either through manually clearing it, inserting it, when it runs out of operators, or fails to execute them,
 /// the plan will be recomputed in the next fixed frame.
 #[derive(Component, Clone, Default, PartialEq, Eq, Reflect, Debug, Deref, DerefMut)]
 #[reflect(Component)]
-#[require(Props)]
+#[require(Props, RandomSeed)]
 pub struct Plan {
     /// The queue of planned [`Operator`]s to execute. This will get [`VecDeque::pop_front`]ed during plan execution.
     #[reflect(ignore)]
     #[deref]
     pub operators_left: VecDeque<PlannedOperator>,
     /// All [`Operator`]s that were in [`Plan::operators_left`] when the plan was created.
+    /// [`Parallel`] steps are represented by the entity holding the [`Parallel`] task.
     pub operators_total: Vec<Entity>,
     /// The [`Mtr`] of the full plan when it was created.
     pub mtr: Mtr,
@@ -40,7 +49,7 @@ impl Plan {
 /// An entry in [`Plan::operators_left`], representing an operator that is either currently executing or waiting to execute.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct PlannedOperator {
-    /// The [`Entity`] of the [`Operator`].
+    /// The [`Entity`] of the [`Operator`], or of the [`Parallel`] task if [`PlannedOperator::parallel`] is set.
     pub entity: Entity,
     /// The [`Effect`]s of the operator to be applied after it completes. Does not include effects that are [`Effect::plan_only`].
     /// The last operator of a compound task will also inherit effects from higher-up compound tasks.
@@ -48,6 +57,130 @@ pub struct PlannedOperator {
     /// The [`Condition`]s that need to be fulfilled for the operator to be run.
     /// The first operator of a compound task will also inherit conditions from higher-up compound tasks.
     pub conditions: Vec<Entity>,
+    /// The concurrently executing branches if this step was planned by a [`Parallel`] task, and `None` for regular [`Operator`]s.
+    pub parallel: Option<PlannedParallel>,
+    /// Whether the operator has already started running, i.e. whether its [`OperatorHook::Enter`] was run.
+    pub started: bool,
+}
+
+/// A step of a [`Plan`] that executes several branches of operators concurrently. Created by [`Parallel`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannedParallel {
+    /// The policy deciding when this step is done.
+    pub policy: ParallelPolicy,
+    /// The branches executing concurrently, one for each subtask of the [`Parallel`] task.
+    pub branches: Vec<PlannedBranch>,
+}
+
+impl PlannedParallel {
+    /// The status of the whole step according to its [`ParallelPolicy`] and the status of its branches.
+    pub fn status(&self) -> OperatorStatus {
+        let mut statuses = self.branches.iter().map(|branch| branch.status);
+        match self.policy {
+            ParallelPolicy::AllSucceed => {
+                if statuses
+                    .clone()
+                    .any(|status| status == OperatorStatus::Failure)
+                {
+                    OperatorStatus::Failure
+                } else if statuses.all(|status| status == OperatorStatus::Success) {
+                    OperatorStatus::Success
+                } else {
+                    OperatorStatus::Ongoing
+                }
+            }
+            ParallelPolicy::AnySucceeds => {
+                if statuses
+                    .clone()
+                    .any(|status| status == OperatorStatus::Success)
+                {
+                    OperatorStatus::Success
+                } else if statuses.all(|status| status == OperatorStatus::Failure) {
+                    OperatorStatus::Failure
+                } else {
+                    OperatorStatus::Ongoing
+                }
+            }
+            ParallelPolicy::FirstFinished => statuses
+                .find(|&status| status != OperatorStatus::Ongoing)
+                .unwrap_or(OperatorStatus::Ongoing),
+        }
+    }
+}
+
+/// A single branch of a [`PlannedParallel`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlannedBranch {
+    /// The queue of planned [`Operator`]s of this branch. This will get [`VecDeque::pop_front`]ed during plan execution.
+    pub operators_left: VecDeque<PlannedOperator>,
+    /// [`OperatorStatus::Ongoing`] while the branch is running. Once all operators succeeded, this is [`OperatorStatus::Success`],
+    /// and if any operator failed, this is [`OperatorStatus::Failure`].
+    pub status: OperatorStatus,
+}
+
+/// The last failure of the [`Plan`] of an entity. Inserted automatically when a step of the plan fails, and kept until the next failure.
+/// Useful for in-game diagnostics. See also [`PlanFailed`], which is triggered at the same time.
+#[derive(Component, Clone, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct PlanFailure {
+    /// Why the plan failed.
+    pub reason: PlanFailureReason,
+    /// The change tick of the [`World`] at the time of the failure.
+    pub tick: Tick,
+}
+
+impl PlanFailure {
+    /// The entity that caused the failure, i.e. the unfulfilled [`Condition`], the failed [`Operator`] or the mismatched [`Effect`].
+    pub fn entity(&self) -> Entity {
+        self.reason.entity()
+    }
+}
+
+/// The reason a [`Plan`] failed. See [`PlanFailed`].
+#[derive(Debug, Clone, PartialEq, Eq, Reflect)]
+pub enum PlanFailureReason {
+    /// A [`Condition`] of the current step was not fulfilled.
+    UnmetCondition {
+        /// The entity holding the unfulfilled [`Condition`].
+        condition: Entity,
+        /// The entity holding the [`Operator`] of the step, or the [`Parallel`] task if the step runs parallel branches.
+        operator: Entity,
+    },
+    /// The [`Operator`] of the current step returned [`OperatorStatus::Failure`].
+    OperatorFailed {
+        /// The entity holding the [`Operator`].
+        operator: Entity,
+    },
+    /// The system of the [`Operator`] of the current step could not be run or returned an error.
+    SystemError {
+        /// The entity holding the [`Operator`].
+        operator: Entity,
+        /// The formatted error.
+        error: String,
+    },
+    /// The entity of the current step no longer exists or no longer holds an [`Operator`].
+    MissingOperator {
+        /// The entity that was expected to hold the [`Operator`].
+        operator: Entity,
+    },
+    /// The properties did not match an [`Effect::verify`] effect of the current step after it succeeded. See [`EffectMismatch`].
+    EffectMismatch {
+        /// The entity holding the [`Effect`].
+        effect: Entity,
+    },
+}
+
+impl PlanFailureReason {
+    /// The entity that caused the failure, i.e. the unfulfilled [`Condition`], the failed [`Operator`] or the mismatched [`Effect`].
+    pub fn entity(&self) -> Entity {
+        match self {
+            Self::UnmetCondition { condition, .. } => *condition,
+            Self::OperatorFailed { operator }
+            | Self::SystemError { operator, .. }
+            | Self::MissingOperator { operator } => *operator,
+            Self::EffectMismatch { effect } => *effect,
+        }
+    }
 }
 
 /// An [`EntityEvent`] for logging a given plan via [`info!`]
@@ -73,6 +206,7 @@ pub(crate) fn log_plan(
     log: On<LogPlan>,
     plans: Query<&Plan, Allow<Disabled>>,
     names: Query<NameOrEntity, Allow<Disabled>>,
+    conditions: Query<&Condition, Allow<Disabled>>,
 ) -> Result {
     let plan_entity = log.entity;
     let plan = plans.get(plan_entity)?;
@@ -88,21 +222,7 @@ pub(crate) fn log_plan(
         plan.operators_left.len()
     ));
     for operator in &plan.operators_left {
-        let operator_name = name(operator.entity)?;
-        log.push_str(&format!("  - {operator_name}:\n"));
-        log.push_str(&format!("    - effects ({}):\n", operator.effects.len()));
-        for effect in &operator.effects {
-            let effect_name = name(*effect)?;
-            log.push_str(&format!("      - {effect_name}\n"));
-        }
-        log.push_str(&format!(
-            "    - conditions ({}):\n",
-            operator.conditions.len()
-        ));
-        for condition in &operator.conditions {
-            let condition_name = name(*condition)?;
-            log.push_str(&format!("      - {condition_name}\n"));
-        }
+        log_operator(&mut log, operator, 1, &name, &conditions)?;
     }
     log.push_str(&format!(
         "- total operators ({})\n",
@@ -115,3 +235,51 @@ pub(crate) fn log_plan(
     info!("{}", log.trim());
     Ok(())
 }
+
+fn log_operator(
+    log: &mut String,
+    operator: &PlannedOperator,
+    depth: usize,
+    name: &impl Fn(Entity) -> Result<String, QueryEntityError>,
+    conditions: &Query<&Condition, Allow<Disabled>>,
+) -> Result {
+    let indent = "  ".repeat(depth);
+    let operator_name = name(operator.entity)?;
+    log.push_str(&format!("{indent}- {operator_name}:\n"));
+    log.push_str(&format!(
+        "{indent}  - effects ({}):\n",
+        operator.effects.len()
+    ));
+    for effect in &operator.effects {
+        let effect_name = name(*effect)?;
+        log.push_str(&format!("{indent}    - {effect_name}\n"));
+    }
+    log.push_str(&format!(
+        "{indent}  - conditions ({}):\n",
+        operator.conditions.len()
+    ));
+    for condition in &operator.conditions {
+        let condition_name = name(*condition)?;
+        match conditions.get(*condition) {
+            Ok(expr) => log.push_str(&format!("{indent}    - {condition_name}: {expr}\n")),
+            Err(_) => log.push_str(&format!("{indent}    - {condition_name}\n")),
+        }
+    }
+    if let Some(parallel) = &operator.parallel {
+        log.push_str(&format!(
+            "{indent}  - branches ({}, {:?}):\n",
+            parallel.branches.len(),
+            parallel.policy
+        ));
+        for (i, branch) in parallel.branches.iter().enumerate() {
+            log.push_str(&format!(
+                "{indent}    - branch {i} ({:?}):\n",
+                branch.status
+            ));
+            for operator in &branch.operators_left {
+                log_operator(log, operator, depth + 3, name, conditions)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! Contains the [`ReplanPolicy`] component for replanning periodically.
+
+use bevy_ecs::{lifecycle::HookContext, world::DeferredWorld};
+use bevy_time::{Time, Timer, TimerMode};
+use core::time::Duration;
+
+use crate::prelude::*;
+
+/// Decides when the [`Plan`] of an entity is recomputed, in addition to [`UpdatePlan`] being triggered manually.
+///
+/// A plan is always recomputed when it ran out of operators or failed. The policies below trigger [`UpdatePlan`] on top of that,
+/// so like with a manual [`UpdatePlan`], the running plan is only replaced by one with a higher priority according to its [`Mtr`](crate::plan::mtr::Mtr).
+/// This way, a periodic replan never interrupts an ongoing [`Sequence`] for something less important.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_bae::prelude::*;
+/// # use core::time::Duration;
+/// # fn attack(_: In<OperatorInput>) -> OperatorStatus { OperatorStatus::Ongoing }
+/// # fn flee(_: In<OperatorInput>) -> OperatorStatus { OperatorStatus::Ongoing }
+/// fn spawn_npc(mut commands: Commands) {
+///     commands.spawn((
+///         Plan::new(),
+///         // Check twice a second whether fleeing became possible
+///         ReplanPolicy::every(Duration::from_millis(500)),
+///         Select,
+///         tasks![
+///             (Operator::new(flee), conditions![Condition::lt("health", 20.0)]),
+///             Operator::new(attack),
+///         ],
+///     ));
+/// }
+/// ```
+#[derive(Component, Clone, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+#[component(on_insert = Self::on_insert_hook, on_replace = Self::on_replace_hook)]
+pub enum ReplanPolicy {
+    /// Only replans when the plan ran out of operators or failed. This is the default, and the same as not inserting a [`ReplanPolicy`] at all.
+    #[default]
+    OnFailure,
+    /// Replans whenever the timer finishes. The timer is ticked with the [`Time`] of the schedule of the [`BaePlugin`],
+    /// and never finishes if there is no [`Time`] resource.
+    /// Created with [`ReplanPolicy::every`].
+    Timer(Timer),
+    /// Replans every `every` runs of the schedule of the [`BaePlugin`], i.e. every `every` fixed ticks by default.
+    /// Created with [`ReplanPolicy::every_ticks`].
+    Ticks {
+        /// The number of runs between two replans.
+        every: u32,
+        /// The number of runs since the last replan.
+        elapsed: u32,
+    },
+    /// Replans when a property read by the domain's conditions changes.
+    /// Inserts [`WatchProps::conditions`] if the entity holds no [`WatchProps`] yet, so use that component directly for more control.
+    /// The inserted [`WatchProps`] is removed again when the policy is replaced or removed.
+    OnPropChange,
+}
+
+impl ReplanPolicy {
+    /// Replans every time the given duration passed.
+    pub fn every(duration: Duration) -> Self {
+        Self::Timer(Timer::new(duration, TimerMode::Repeating))
+    }
+
+    /// Replans every `ticks` runs of the schedule of the [`BaePlugin`].
+    pub fn every_ticks(ticks: u32) -> Self {
+        Self::Ticks {
+            every: ticks,
+            elapsed: 0,
+        }
+    }
+
+    fn on_insert_hook(mut world: DeferredWorld, context: HookContext) {
+        if world.get::<Self>(context.entity) != Some(&Self::OnPropChange) {
+            return;
+        }
+        let entity = world.entity(context.entity);
+        // When replacing an earlier `OnPropChange`, its `WatchProps` are still there until the removal queued by `on_replace_hook` runs
+        if entity.contains::<WatchProps>() && !entity.contains::<PolicyWatchProps>() {
+            return;
+        }
+        world
+            .commands()
+            .entity(context.entity)
+            .insert((WatchProps::conditions(), PolicyWatchProps));
+    }
+
+    fn on_replace_hook(mut world: DeferredWorld, context: HookContext) {
+        if world.entity(context.entity).contains::<PolicyWatchProps>() {
+            world
+                .commands()
+                .entity(context.entity)
+                .try_remove::<(WatchProps, PolicyWatchProps)>();
+        }
+    }
+}
+
+/// Marks [`WatchProps`] that were inserted by [`ReplanPolicy::OnPropChange`], so that they are removed along with the policy.
+#[derive(Component)]
+struct PolicyWatchProps;
+
+pub(crate) fn replan_on_policy(
+    mut planners: Query<(Entity, NameOrEntity, &mut ReplanPolicy, &Plan)>,
+    time: Option<Res<Time>>,
+    mut commands: Commands,
+) {
+    for (entity, name, mut policy, plan) in &mut planners {
+        let replan = match &mut *policy {
+            ReplanPolicy::OnFailure | ReplanPolicy::OnPropChange => false,
+            // Without a time source, e.g. when the `TimePlugin` is missing, the timer never advances
+            ReplanPolicy::Timer(timer) => time
+                .as_ref()
+                .is_some_and(|time| timer.tick(time.delta()).just_finished()),
+            ReplanPolicy::Ticks { every, elapsed } => {
+                *elapsed += 1;
+                let replan = *elapsed >= *every;
+                if replan {
+                    *elapsed = 0;
+                }
+                replan
+            }
+        };
+        // Empty plans are replanned anyways
+        if replan && !plan.is_empty() {
+            debug!(entity=?name.entity, name=?name.name, "replan policy triggered replan");
+            commands.entity(entity).trigger(UpdatePlan::new);
+        }
+    }
+}
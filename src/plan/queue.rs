@@ -0,0 +1,199 @@
+//! Contains the [`PlanningQueue`] resource for spreading the cost of planning over several frames.
+
+use alloc::collections::VecDeque;
+use bevy_ecs::entity::EntityHashSet;
+use bevy_platform::time::Instant;
+use core::time::Duration;
+
+use crate::{
+    plan::{batch::plan_in_parallel, update::run_update_plan},
+    prelude::*,
+};
+
+/// Limits how much planning happens per run of the schedule of the [`BaePlugin`].
+///
+/// By default, every [`UpdatePlan`] is handled right away, so when hundreds of agents replan at once, they all decompose their domain in the same frame.
+/// With a [`PlanningBudget`] other than [`PlanningBudget::Unlimited`], [`UpdatePlan`] instead puts the entity into this queue,
+/// and the queue is worked off right before the [`Plan`]s are executed, only doing as much planning as the budget allows.
+/// Everything else waits for the next run, which keeps the frame time flat in crowded scenes at the cost of agents reacting a bit later.
+///
+/// Agents are planned in the order in which they requested it, and requesting it again while waiting does not lose their place.
+/// Agents whose [`Plan`] is empty are idle until they are planned, so they are planned before all others.
+/// Agents that never find a valid plan request a new one in every run, so once the agents that are still running a plan
+/// were passed over for [`PlanningQueue::MAX_SKIPPED_RUNS`] runs in a row, they are planned first for one run instead.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_bae::prelude::*;
+/// # let mut app = App::new();
+/// app.add_plugins(BaePlugin::default())
+///     .insert_resource(PlanningQueue::new(PlanningBudget::Planners(20)));
+/// ```
+///
+/// Set [`PlanningQueue::parallel`] to additionally spread the planning of the queued entities over multiple threads.
+#[derive(Resource, Debug, Default)]
+pub struct PlanningQueue {
+    /// How much planning may happen per run. Default is [`PlanningBudget::Unlimited`].
+    pub budget: PlanningBudget,
+    /// Whether to decompose the domains of the queued entities in parallel on the [`ComputeTaskPool`](bevy_tasks::ComputeTaskPool).
+    /// When set, [`UpdatePlan`] always goes through the queue, even with [`PlanningBudget::Unlimited`],
+    /// and all entities planned in a run are decomposed at once before their new [`Plan`]s are inserted.
+    ///
+    /// Only domains made of [`Operator`]s, [`Select`]s, [`Sequence`]s and [`Condition`]s can be decomposed in parallel,
+    /// since every other [`CompoundTask`] and [`SystemCondition`] runs a system with exclusive access to the [`World`].
+    /// Entities with other domains are still planned one after another, so they can safely be mixed with the others.
+    /// [`PlanningBudget::Time`] cannot be checked while planning in parallel, so it always plans one entity after another.
+    ///
+    /// This comes with two limitations:
+    /// - Domains decomposed in parallel neither use nor fill the [`PlanCache`].
+    /// - All entities of a run are decomposed against the same state of the [`World`]. Whatever observers of [`ReplacePlan`] or the
+    ///   [`OperatorHook::Abort`] of replaced steps change while the plans of earlier entities are applied is not seen by the entities planned after them in the same run.
+    ///
+    /// Default is `false`.
+    pub parallel: bool,
+    waiting: VecDeque<Entity>,
+    queued: EntityHashSet,
+    /// The number of runs in a row in which agents that are still running a plan were waiting, but none of them was planned.
+    skipped_runs: u32,
+}
+
+/// The budget of a [`PlanningQueue`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect)]
+pub enum PlanningBudget {
+    /// Plans are computed as soon as [`UpdatePlan`] is triggered, without using the queue.
+    #[default]
+    Unlimited,
+    /// Plans at most this many entities per run.
+    Planners(usize),
+    /// Keeps planning entities until this much time has passed in the current run.
+    /// At least one entity is planned per run, so that the queue always makes progress.
+    Time(Duration),
+}
+
+impl PlanningQueue {
+    /// The number of runs in a row that agents still running a plan may be passed over for agents with an empty [`Plan`].
+    pub const MAX_SKIPPED_RUNS: u32 = 3;
+
+    /// Creates a new empty queue with the given budget.
+    pub fn new(budget: PlanningBudget) -> Self {
+        Self {
+            budget,
+            ..Self::default()
+        }
+    }
+
+    /// Sets [`PlanningQueue::parallel`].
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Returns the number of entities waiting to be planned.
+    pub fn len(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Returns whether no entity is waiting to be planned.
+    pub fn is_empty(&self) -> bool {
+        self.waiting.is_empty()
+    }
+
+    /// Returns whether the given entity is waiting to be planned.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.queued.contains(&entity)
+    }
+
+    /// Puts the entity at the end of the queue, unless it is already waiting.
+    pub(crate) fn push(&mut self, entity: Entity) {
+        if self.queued.insert(entity) {
+            self.waiting.push_back(entity);
+        }
+    }
+}
+
+pub(crate) fn process_planning_queue(
+    world: &mut World,
+    mut scratch: Local<Vec<Entity>>,
+    mut effects: Local<QueryState<Entity, With<Effect>>>,
+) {
+    let Some(mut queue) = world.get_resource_mut::<PlanningQueue>() else {
+        return;
+    };
+    if queue.is_empty() {
+        return;
+    }
+    let budget = queue.budget;
+    let parallel = queue.parallel && !matches!(budget, PlanningBudget::Time(_));
+    let waiting = core::mem::take(&mut queue.waiting);
+    // Planners with an empty plan are idle until they get planned, so they go first,
+    // unless the running ones were passed over too often, e.g. by planners that never find a plan
+    let running_first = queue.skipped_runs >= PlanningQueue::MAX_SKIPPED_RUNS;
+    let (empty, running): (Vec<_>, Vec<_>) = waiting
+        .into_iter()
+        .partition(|&entity| world.get::<Plan>(entity).is_none_or(|plan| plan.is_empty()));
+    let (empty_count, running_count) = (empty.len(), running.len());
+    if running_first {
+        scratch.extend(running);
+        scratch.extend(empty);
+    } else {
+        scratch.extend(empty);
+        scratch.extend(running);
+    }
+
+    let mut planned = 0;
+    let mut processed = 0;
+    if parallel {
+        processed = match budget {
+            PlanningBudget::Planners(max) => max.min(scratch.len()),
+            _ => scratch.len(),
+        };
+        let mut queue = world.resource_mut::<PlanningQueue>();
+        for entity in &scratch[..processed] {
+            queue.queued.remove(entity);
+        }
+        let batch = scratch[..processed]
+            .iter()
+            .copied()
+            .filter(|&entity| world.get_entity(entity).is_ok())
+            .collect::<Vec<_>>();
+        planned = batch.len();
+        plan_in_parallel(world, &mut effects, &batch);
+    } else {
+        let start = Instant::now();
+        for &entity in scratch.iter() {
+            let exhausted = match budget {
+                PlanningBudget::Unlimited => false,
+                PlanningBudget::Planners(max) => planned >= max,
+                PlanningBudget::Time(max) => planned > 0 && start.elapsed() >= max,
+            };
+            if exhausted {
+                break;
+            }
+            processed += 1;
+            world.resource_mut::<PlanningQueue>().queued.remove(&entity);
+            if world.get_entity(entity).is_err() {
+                continue;
+            }
+            run_update_plan(world, entity);
+            planned += 1;
+        }
+    }
+    let mut queue = world.resource_mut::<PlanningQueue>();
+    let planned_running = if running_first {
+        processed > 0
+    } else {
+        processed > empty_count
+    };
+    if running_count == 0 || planned_running {
+        queue.skipped_runs = 0;
+    } else {
+        queue.skipped_runs += 1;
+    }
+    // The entities that are left are planned first in the next run,
+    // and entities queued during planning wait behind them
+    for &entity in scratch[processed..].iter().rev() {
+        queue.waiting.push_front(entity);
+    }
+    scratch.clear();
+    debug!(planned, waiting = queue.len(), "processed planning queue");
+}
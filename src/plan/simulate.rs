@@ -0,0 +1,138 @@
+//! Contains [`SimulatePlanExt`] for computing plans without executing them.
+
+use crate::{
+    condition::system::check_planning_condition,
+    plan::{
+        mtr::Mtr,
+        update::{single_operator_plan, task_root},
+    },
+    prelude::*,
+    task::compound::{DecomposeInput, DecomposeResult, Subtask},
+};
+
+/// The outcome of [`SimulatePlanExt::simulate_plan`].
+#[derive(Clone)]
+pub struct SimulatedPlan {
+    /// The plan the planner would execute. Empty if no valid plan was found.
+    pub plan: Plan,
+    /// The properties predicted after the whole plan ran, i.e. with the [`Effect`]s of all planned tasks applied.
+    /// If no valid plan was found, these are the properties that were passed in.
+    pub props: Props,
+}
+
+impl SimulatedPlan {
+    /// The [`Mtr`] of the simulated plan, which decides whether it would interrupt a running plan.
+    pub fn mtr(&self) -> &Mtr {
+        &self.plan.mtr
+    }
+
+    /// Returns whether no valid plan was found.
+    pub fn is_empty(&self) -> bool {
+        self.plan.is_empty()
+    }
+}
+
+/// Extension trait for asking "what would this planner do if…" without touching the live entity.
+pub trait SimulatePlanExt {
+    /// Runs the full decomposition of the domain of the planner against the given hypothetical [`Props`] instead of its own,
+    /// and returns the resulting plan together with the predicted properties.
+    ///
+    /// Nothing is inserted into the planner, no [`ReplacePlan`] is triggered, and the running plan is neither aborted nor taken into account,
+    /// i.e. the simulated plan is the best plan for the given properties, even if [`UpdatePlan`] would keep the running plan instead.
+    /// [`SystemCondition`]s and custom [`CompoundTask`]s still run their systems, so they must not have side effects for the simulation to be pure.
+    ///
+    /// Returns an error if the planner has no tasks.
+    ///
+    /// ```
+    /// # use bevy::prelude::*;
+    /// # use bevy_bae::prelude::*;
+    /// fn log_plan_when_hurt(world: &mut World, npc: Entity) -> Result {
+    ///     let mut props = world.entity(npc).get::<Props>().cloned().unwrap_or_default();
+    ///     props.set("health", 10.0);
+    ///     let simulated = world.simulate_plan(npc, props)?;
+    ///     info!("when hurt, the NPC would run {:?}", simulated.plan.operators_total);
+    ///     Ok(())
+    /// }
+    /// ```
+    fn simulate_plan(&mut self, planner: Entity, props: Props) -> Result<SimulatedPlan>;
+}
+
+impl SimulatePlanExt for World {
+    fn simulate_plan(&mut self, planner: Entity, props: Props) -> Result<SimulatedPlan> {
+        self.run_system_cached_with(simulate_plan_inner, (planner, props))?
+    }
+}
+
+fn simulate_plan_inner(
+    In((root, props)): In<(Entity, Props)>,
+    world: &mut World,
+    mut conditions: Local<QueryState<(Entity, &Condition)>>,
+    mut effects: Local<QueryState<(Entity, &Effect)>>,
+) -> Result<SimulatedPlan> {
+    let task_root = task_root(world, root);
+    let mut world_state = props;
+    let mut initial_conditions = Vec::new();
+    if let Some(condition_relations) = world.get::<Conditions>(task_root).cloned() {
+        for entity in &condition_relations {
+            let is_fulfilled =
+                check_planning_condition(world, &mut conditions, &mut world_state, root, entity);
+            match is_fulfilled {
+                Some(true) => initial_conditions.push(entity),
+                Some(false) => {
+                    return Ok(SimulatedPlan {
+                        plan: Plan::default(),
+                        props: world_state,
+                    });
+                }
+                None => {}
+            }
+        }
+    }
+
+    let Some(task) = Subtask::read(world, task_root) else {
+        return Err(BevyError::from("Called `simulate_plan` for an entity without any tasks. Ensure it has either an `Operator` or a `CompoundTask` like `Select` or `Sequence`, or points to one with `UsesDomain`".to_string()));
+    };
+    let (mut plan, mut world_state) = if let Some(compound_task) = task.compound_task {
+        let ctx = DecomposeInput {
+            world_state: world_state.clone(),
+            plan: Plan::default(),
+            planner: root,
+            compound_task: task_root,
+            // Don't let the running plan reject anything
+            previous_mtr: Mtr::none(),
+            conditions: initial_conditions,
+            skip: 0,
+        };
+        let result = world.run_system_with(compound_task.decompose, ctx)?;
+        world.flush();
+        match result {
+            DecomposeResult::Success { plan, world_state } => (plan, world_state),
+            DecomposeResult::Failure | DecomposeResult::Rejection => {
+                return Ok(SimulatedPlan {
+                    plan: Plan::default(),
+                    props: world_state,
+                });
+            }
+        }
+    } else {
+        (
+            single_operator_plan(task.entity, initial_conditions),
+            world_state,
+        )
+    };
+
+    // Mirror what inserting the plan would do
+    if !plan.is_empty()
+        && let Some(effect_relations) = world.get::<Effects>(task_root)
+    {
+        for (entity, effect) in effects.iter_many(world, effect_relations) {
+            effect.apply(&mut world_state);
+            plan.back_mut().unwrap().effects.push(entity);
+        }
+    }
+    plan.operators_total = plan.operators_left.iter().map(|op| op.entity).collect();
+    Ok(SimulatedPlan {
+        plan,
+        props: world_state,
+    })
+}
@@ -0,0 +1,267 @@
+//! Contains the [`DecompositionTrace`] component for inspecting how a plan was found.
+
+use core::fmt::{self, Display};
+
+use crate::{plan::mtr::Mtr, prelude::*, task::compound::DecomposeResult};
+
+/// Records the search done while decomposing the domain the last time the [`Plan`] of this entity was updated.
+/// Useful for finding out why an agent picked an unexpected branch.
+///
+/// Insert this component next to the [`Plan`] to opt into recording. Every [`UpdatePlan`] replaces the recorded trace,
+/// including the ones that end up keeping the running plan.
+/// The trace holds every task that was visited, every condition that was evaluated together with its result and the properties it read,
+/// subtasks that were rejected because of their [`Mtr`], and the outcome of every nested [`CompoundTask`].
+/// Its [`Display`] implementation renders it as an indented tree.
+///
+/// Recording allocates for every visited task, so only use this while debugging.
+/// Planners with this component bypass the [`PlanCache`], and are never planned in parallel by the [`PlanningQueue`].
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_bae::prelude::*;
+/// fn print_traces(traces: Query<&DecompositionTrace, Changed<DecompositionTrace>>) {
+///     for trace in &traces {
+///         info!("{trace}");
+///     }
+/// }
+/// ```
+#[derive(Component, Clone, Debug, Default)]
+pub struct DecompositionTrace {
+    /// The root task of the domain, or `None` if the plan was not updated since this component was inserted.
+    pub root: Option<TracedTask>,
+}
+
+/// A task visited during decomposition. See [`DecompositionTrace`].
+#[derive(Clone, Debug)]
+pub struct TracedTask {
+    /// The entity holding the task.
+    pub entity: Entity,
+    /// The [`Name`] of the entity, if any.
+    pub name: Option<Name>,
+    /// Which alternative decomposition of the task was requested, where `0` is the first valid one.
+    /// Greater values appear when a [`Select`] counts alternatives or a [`Backtrack`]ing [`Sequence`] retries a subtask.
+    pub alternative: usize,
+    /// The conditions of the task that were evaluated, in order.
+    pub conditions: Vec<TracedCondition>,
+    /// The subtasks visited while decomposing the task, in order.
+    pub subtasks: Vec<TracedTask>,
+    /// How the decomposition of the task ended.
+    pub outcome: TraceOutcome,
+}
+
+/// A condition evaluated during decomposition. See [`DecompositionTrace`].
+#[derive(Clone, Debug)]
+pub struct TracedCondition {
+    /// The entity holding the condition.
+    pub entity: Entity,
+    /// The [`Name`] of the entity, if any.
+    pub name: Option<Name>,
+    /// The rendered [`Condition`], or `None` if the entity only holds a [`SystemCondition`].
+    pub expr: Option<String>,
+    /// The properties read by the condition, as far as they are known from its [`ConditionExpr`], and their values at the time.
+    pub props: Vec<(Ustr, Value)>,
+    /// Whether the condition was fulfilled.
+    pub fulfilled: bool,
+}
+
+/// How the decomposition of a [`TracedTask`] ended.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum TraceOutcome {
+    /// The task was decomposed.
+    Success,
+    /// The task could not be decomposed, e.g. because one of its conditions is not fulfilled or a nested [`CompoundTask`] failed.
+    #[default]
+    Failure,
+    /// The task was not tried, because the running plan has a higher priority.
+    Rejected {
+        /// The [`Mtr`] the task would have had.
+        mtr: Mtr,
+        /// The [`Mtr`] of the running plan.
+        previous_mtr: Mtr,
+    },
+    /// A subtask was rejected, which aborts the whole decomposition and keeps the running plan.
+    Aborted,
+}
+
+impl From<&DecomposeResult> for TraceOutcome {
+    fn from(result: &DecomposeResult) -> Self {
+        match result {
+            DecomposeResult::Success { .. } => Self::Success,
+            DecomposeResult::Failure => Self::Failure,
+            DecomposeResult::Rejection => Self::Aborted,
+        }
+    }
+}
+
+impl Display for DecompositionTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.root {
+            Some(root) => {
+                writeln!(f, "decomposition trace:")?;
+                root.fmt_tree(f, 0)
+            }
+            None => write!(f, "decomposition trace: nothing planned yet"),
+        }
+    }
+}
+
+impl TracedTask {
+    fn fmt_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        write!(f, "{indent}- {}", label(self.entity, &self.name))?;
+        if self.alternative > 0 {
+            write!(f, " (alternative {})", self.alternative)?;
+        }
+        match &self.outcome {
+            TraceOutcome::Success => writeln!(f, ": success")?,
+            TraceOutcome::Failure => writeln!(f, ": failure")?,
+            TraceOutcome::Rejected { mtr, previous_mtr } => writeln!(
+                f,
+                ": rejected, mtr {mtr} has a lower priority than the running {previous_mtr}"
+            )?,
+            TraceOutcome::Aborted => writeln!(f, ": aborted")?,
+        }
+        for condition in &self.conditions {
+            write!(
+                f,
+                "{indent}  - condition {}: {} is {}",
+                label(condition.entity, &condition.name),
+                condition.expr.as_deref().unwrap_or("<system>"),
+                condition.fulfilled
+            )?;
+            if !condition.props.is_empty() {
+                let props = condition
+                    .props
+                    .iter()
+                    .map(|(name, value)| format!("{name} = {value:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, " ({props})")?;
+            }
+            writeln!(f)?;
+        }
+        for subtask in &self.subtasks {
+            subtask.fmt_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn label(entity: Entity, name: &Option<Name>) -> String {
+    name.as_ref()
+        .map(|name| format!("{entity} ({name})"))
+        .unwrap_or_else(|| format!("{entity}"))
+}
+
+/// Collects the trace while an entity with a [`DecompositionTrace`] is being planned.
+/// Only exists during planning, so all recording functions do nothing otherwise.
+#[derive(Resource)]
+struct TraceRecorder {
+    /// The tasks currently being decomposed, outermost first.
+    stack: Vec<TracedTask>,
+}
+
+/// Starts recording if the planner has a [`DecompositionTrace`], with the given task as the root. Returns whether recording started.
+pub(crate) fn begin(world: &mut World, planner: Entity, task_root: Entity) -> bool {
+    if !world
+        .get_entity(planner)
+        .is_ok_and(|planner| planner.contains::<DecompositionTrace>())
+    {
+        return false;
+    }
+    let root = traced_task(world, task_root, 0);
+    world.insert_resource(TraceRecorder { stack: vec![root] });
+    true
+}
+
+/// Stops recording and stores the trace in the [`DecompositionTrace`] of the planner.
+pub(crate) fn finish(world: &mut World, planner: Entity, outcome: TraceOutcome) {
+    let Some(mut recorder) = world.remove_resource::<TraceRecorder>() else {
+        return;
+    };
+    let Some(mut root) = recorder.stack.drain(..).next() else {
+        return;
+    };
+    root.outcome = outcome;
+    if let Some(mut trace) = world.get_mut::<DecompositionTrace>(planner) {
+        trace.root = Some(root);
+    }
+}
+
+/// Records that decomposing the task starts. Must be followed by [`exit`].
+pub(crate) fn enter(world: &mut World, entity: Entity, alternative: usize) {
+    if !world.contains_resource::<TraceRecorder>() {
+        return;
+    }
+    let task = traced_task(world, entity, alternative);
+    world.resource_mut::<TraceRecorder>().stack.push(task);
+}
+
+/// Records how decomposing the task that was last [`enter`]ed ended.
+pub(crate) fn exit(world: &mut World, result: &DecomposeResult) {
+    let Some(mut recorder) = world.get_resource_mut::<TraceRecorder>() else {
+        return;
+    };
+    if recorder.stack.len() < 2 {
+        return;
+    }
+    let mut task = recorder.stack.pop().unwrap();
+    task.outcome = result.into();
+    recorder.stack.last_mut().unwrap().subtasks.push(task);
+}
+
+/// Records that the subtask was not tried because of its [`Mtr`].
+pub(crate) fn reject(world: &mut World, entity: Entity, mtr: &Mtr, previous_mtr: &Mtr) {
+    if !world.contains_resource::<TraceRecorder>() {
+        return;
+    }
+    let mut task = traced_task(world, entity, 0);
+    task.outcome = TraceOutcome::Rejected {
+        mtr: mtr.clone(),
+        previous_mtr: previous_mtr.clone(),
+    };
+    if let Some(parent) = world.resource_mut::<TraceRecorder>().stack.last_mut() {
+        parent.subtasks.push(task);
+    }
+}
+
+/// Records the result of evaluating the condition for the task that was last [`enter`]ed.
+pub(crate) fn condition(
+    world: &mut World,
+    entity: Entity,
+    world_state: &mut Props,
+    fulfilled: bool,
+) {
+    if !world.contains_resource::<TraceRecorder>() {
+        return;
+    }
+    let condition = world.get::<Condition>(entity);
+    let mut names = Vec::new();
+    if let Some(condition) = condition {
+        condition.read_props(&mut names);
+    }
+    let traced = TracedCondition {
+        entity,
+        name: world.get::<Name>(entity).cloned(),
+        expr: condition.map(ToString::to_string),
+        props: names
+            .into_iter()
+            .map(|name| (name, *world_state.entry(name).or_default()))
+            .collect(),
+        fulfilled,
+    };
+    if let Some(task) = world.resource_mut::<TraceRecorder>().stack.last_mut() {
+        task.conditions.push(traced);
+    }
+}
+
+fn traced_task(world: &World, entity: Entity, alternative: usize) -> TracedTask {
+    TracedTask {
+        entity,
+        name: world.get::<Name>(entity).cloned(),
+        alternative,
+        conditions: Vec::new(),
+        subtasks: Vec::new(),
+        outcome: TraceOutcome::default(),
+    }
+}
@@ -3,10 +3,13 @@
 use bevy_ecs::error::{DefaultErrorHandler, HandleError as _};
 use bevy_ecs::system::command::run_system_cached_with;
 use bevy_mod_props::PropsExt;
-use core::marker::PhantomData;
 
+use crate::condition::system::check_planning_condition;
 use crate::plan::PlannedOperator;
+use crate::plan::cache::{cache_key, cached_update, store_result};
+use crate::plan::execution::abort_running_step;
 use crate::plan::mtr::Mtr;
+use crate::plan::trace::{self, TraceOutcome};
 use crate::prelude::*;
 use crate::task::compound::{DecomposeInput, DecomposeResult, TypeErasedCompoundTask};
 
@@ -34,25 +37,39 @@ impl UpdatePlan {
     }
 }
 
-/// Event triggered automatically when a plan is replaced.
-#[derive(EntityEvent)]
+/// [`EntityEvent`] triggered automatically after a new plan was computed and inserted, replacing the previous one.
+/// Not triggered when [`UpdatePlan`] keeps the running plan. Triggering this yourself does not change the [`Plan`], use [`UpdatePlan`] for that.
+///
+/// See the [`event`](crate::plan::event) module for the other events triggered during the lifecycle of a plan.
+#[derive(EntityEvent, Debug, Clone, PartialEq, Eq)]
 pub struct ReplacePlan {
     /// The entity holding the [`Plan`] that was replaced.
     #[event_target]
     pub entity: Entity,
     /// The previous value of the [`Plan`]. To read the current value, query it in your observer.
     pub old: Plan,
-    /// Here so users cannot accidentally create a [`ReplacePlan`] when they were
-    /// actually looking for [`UpdatePlan`].
-    _pd: PhantomData<()>,
+}
+
+impl ReplacePlan {
+    /// Creates a new [`ReplacePlan`] event for the given entity and its previous [`Plan`].
+    pub fn new(entity: Entity, old: Plan) -> Self {
+        Self { entity, old }
+    }
 }
 
 pub(crate) fn update_plan(
     update: On<UpdatePlan>,
     mut commands: Commands,
     error_handler: Option<Res<DefaultErrorHandler>>,
+    queue: Option<ResMut<PlanningQueue>>,
 ) {
     let entity = update.entity;
+    if let Some(mut queue) = queue
+        && (queue.budget != PlanningBudget::Unlimited || queue.parallel)
+    {
+        queue.push(entity);
+        return;
+    }
     let error_handler = error_handler.map(|h| *h).unwrap_or_default();
     commands.queue(
         run_system_cached_with(update_plan_inner, UpdatePlan { entity })
@@ -60,6 +77,19 @@ pub(crate) fn update_plan(
     );
 }
 
+/// Computes the plan of the entity right away, bypassing the [`PlanningQueue`].
+pub(crate) fn run_update_plan(world: &mut World, entity: Entity) {
+    let error_handler = world
+        .get_resource::<DefaultErrorHandler>()
+        .copied()
+        .unwrap_or_default();
+    world.commands().queue(
+        run_system_cached_with(update_plan_inner, UpdatePlan { entity })
+            .handle_error_with(error_handler.0),
+    );
+    world.flush();
+}
+
 fn update_plan_inner(
     update: In<UpdatePlan>,
     world: &mut World,
@@ -73,87 +103,182 @@ fn update_plan_inner(
     >,
 ) -> Result {
     let root = update.entity;
+    let task_root = task_root(world, root);
+    let tracing = trace::begin(world, root, task_root);
 
     let mut world_state = world.entity(update.entity).props().clone();
     let mut initial_conditions = Vec::new();
-    if let Some(condition_relations) = world.get::<Conditions>(root) {
-        for (entity, condition) in conditions.iter_many(world, condition_relations) {
-            let is_fulfilled = condition.is_fullfilled(&mut world_state);
-            if !is_fulfilled {
-                world.entity_mut(root).insert(Plan::default());
-                return Ok(());
+    if let Some(condition_relations) = world.get::<Conditions>(task_root).cloned() {
+        for entity in &condition_relations {
+            let is_fulfilled =
+                check_planning_condition(world, &mut conditions, &mut world_state, root, entity);
+            match is_fulfilled {
+                Some(true) => initial_conditions.push(entity),
+                Some(false) => {
+                    trace::finish(world, root, TraceOutcome::Failure);
+                    apply_plan_update(world, &mut effects, root, PlanUpdate::Clear);
+                    return Ok(());
+                }
+                None => {}
             }
-            initial_conditions.push(entity);
         }
     }
 
     let Ok((entity, has_operator, compound_task)) =
         tasks
-            .get(world, root)
+            .get(world, task_root)
             .map(|(entity, has_operator, compound_task)| {
                 (entity, has_operator, compound_task.cloned())
             })
     else {
-        world.entity_mut(root).insert(Plan::default());
-        return Err(BevyError::from("Called `update_plan` for an entity without any tasks. Ensure it has either an `Operator` or a `CompoundTask` like `Select` or `Sequence`".to_string()));
+        trace::finish(world, root, TraceOutcome::Failure);
+        apply_plan_update(world, &mut effects, root, PlanUpdate::Clear);
+        return Err(BevyError::from("Called `update_plan` for an entity without any tasks. Ensure it has either an `Operator` or a `CompoundTask` like `Select` or `Sequence`, or points to one with `UsesDomain`".to_string()));
     };
-    let mut plan = if has_operator {
+    let update = if has_operator {
         // well that was easy: this root has just a single operator
-        Plan {
-            operators_left: [PlannedOperator {
-                entity,
-                effects: vec![],
-                conditions: initial_conditions,
-            }]
-            .into(),
-            mtr: Mtr::default(),
-            operators_total: Vec::new(),
-        }
+        trace::finish(world, root, TraceOutcome::Success);
+        PlanUpdate::Replace(single_operator_plan(entity, initial_conditions))
     } else if let Some(compound_task) = compound_task {
-        let previous_mtr = if let Some(plan) = world.entity(root).get::<Plan>() {
-            plan.mtr.clone()
+        if world
+            .entity(root)
+            .get::<Plan>()
+            .is_none_or(|plan| plan.is_empty())
+            && let Some(mut seed) = world.get_mut::<RandomSeed>(root)
+        {
+            // We are looking for a completely new plan, so allow `RandomSelect`s to make new choices
+            seed.advance();
+        }
+        // A cache hit would skip the search we want to trace
+        let cache_key = if tracing {
+            None
         } else {
-            Mtr::none()
+            cache_key(world, task_root, &mut world_state)
         };
+        if let Some(key) = &cache_key
+            && let Some(update) = cached_update(world, root, task_root, key)
+        {
+            apply_plan_update(world, &mut effects, root, update);
+            return Ok(());
+        }
         let ctx = DecomposeInput {
             world_state,
             plan: Plan::default(),
             planner: root,
-            compound_task: root,
-            previous_mtr: previous_mtr.clone(),
+            compound_task: task_root,
+            previous_mtr: previous_mtr(world, root),
             conditions: initial_conditions,
+            skip: 0,
         };
-        let result = world.run_system_with(compound_task.decompose, ctx)?;
+        let result = world.run_system_with(compound_task.decompose, ctx);
         world.flush();
-
-        match result {
-            DecomposeResult::Success { plan, .. } => {
-                if previous_mtr == plan.mtr
-                    && world.entity(root).get::<Plan>().is_some_and(|prev_plan| {
-                        prev_plan.operators_total.len() == plan.operators_left.len()
-                            && prev_plan
-                                .operators_total
-                                .iter()
-                                .zip(plan.operators_left.iter())
-                                .all(|(a, b)| *a == b.entity)
-                    })
-                {
-                    // We found the same plan we are already running. Just keep that one.
-                    return Ok(());
-                }
-                plan
-            }
-            DecomposeResult::Failure => Plan::default(),
-            DecomposeResult::Rejection => return Ok(()),
+        trace::finish(
+            world,
+            root,
+            result.as_ref().map_or(TraceOutcome::Failure, Into::into),
+        );
+        let result = result?;
+        if let Some(key) = cache_key {
+            store_result(world, task_root, key, &result);
         }
+        PlanUpdate::from_result(world, root, result)
     } else {
         unreachable!(
             "Bevy should guarantee that `AnyOf` contains at least one element that is `Some`"
         )
     };
+    apply_plan_update(world, &mut effects, root, update);
+    Ok(())
+}
+
+/// The outcome of planning for an entity, applied with [`apply_plan_update`].
+pub(crate) enum PlanUpdate {
+    /// Keep the running plan, because the new plan is the same or has a lower priority.
+    Keep,
+    /// The conditions of the domain are not fulfilled, so abort the running plan without replacing it.
+    Clear,
+    /// Replace the running plan with this one.
+    Replace(Plan),
+}
+
+impl PlanUpdate {
+    /// Interprets the result of decomposing the root [`CompoundTask`] of the planner.
+    pub(crate) fn from_result(world: &World, root: Entity, result: DecomposeResult) -> Self {
+        match result {
+            DecomposeResult::Success { plan, .. } => Self::from_plan(world, root, plan),
+            DecomposeResult::Failure => Self::Replace(Plan::default()),
+            DecomposeResult::Rejection => Self::Keep,
+        }
+    }
+
+    /// Replaces the running plan with the newly found one, unless they are the same.
+    pub(crate) fn from_plan(world: &World, root: Entity, plan: Plan) -> Self {
+        if world.get::<Plan>(root).is_some_and(|prev_plan| {
+            prev_plan.mtr == plan.mtr
+                && prev_plan.operators_total.len() == plan.operators_left.len()
+                && prev_plan
+                    .operators_total
+                    .iter()
+                    .zip(plan.operators_left.iter())
+                    .all(|(a, b)| *a == b.entity)
+        }) {
+            // We found the same plan we are already running. Just keep that one.
+            return Self::Keep;
+        }
+        Self::Replace(plan)
+    }
+}
 
+/// Returns the entity whose tasks, conditions and effects make up the domain of the planner.
+/// Planners using a shared domain read their tasks from it, but keep their own props and plan.
+pub(crate) fn task_root(world: &World, root: Entity) -> Entity {
+    world
+        .get::<UsesDomain>(root)
+        .map_or(root, |uses_domain| uses_domain.0)
+}
+
+/// Returns the [`Mtr`] of the running plan, which new plans need to beat.
+pub(crate) fn previous_mtr(world: &World, root: Entity) -> Mtr {
+    world
+        .get::<Plan>(root)
+        .map_or_else(Mtr::none, |plan| plan.mtr.clone())
+}
+
+/// Creates the plan for a domain that consists of a single [`Operator`].
+pub(crate) fn single_operator_plan(operator: Entity, conditions: Vec<Entity>) -> Plan {
+    Plan {
+        operators_left: [PlannedOperator {
+            entity: operator,
+            effects: vec![],
+            conditions,
+            parallel: None,
+            started: false,
+        }]
+        .into(),
+        mtr: Mtr::default(),
+        operators_total: Vec::new(),
+    }
+}
+
+/// Writes the outcome of planning to the planner, and triggers [`ReplacePlan`] if the plan was replaced.
+pub(crate) fn apply_plan_update(
+    world: &mut World,
+    effects: &mut QueryState<Entity, With<Effect>>,
+    root: Entity,
+    update: PlanUpdate,
+) {
+    let mut plan = match update {
+        PlanUpdate::Keep => return,
+        PlanUpdate::Clear => {
+            abort_running_step(world, root);
+            world.entity_mut(root).insert(Plan::default());
+            return;
+        }
+        PlanUpdate::Replace(plan) => plan,
+    };
+    let task_root = task_root(world, root);
     if !plan.is_empty()
-        && let Some(effect_relations) = world.get::<Effects>(root)
+        && let Some(effect_relations) = world.get::<Effects>(task_root)
     {
         for effect in effects.iter_many(world, effect_relations) {
             plan.back_mut().unwrap().effects.push(effect);
@@ -172,11 +297,7 @@ fn update_plan_inner(
         .get::<Plan>()
         .cloned()
         .unwrap_or_default();
+    abort_running_step(world, root);
     world.entity_mut(root).insert(plan);
-    world.trigger(ReplacePlan {
-        entity: root,
-        old: old_plan,
-        _pd: PhantomData,
-    });
-    Ok(())
+    world.trigger(ReplacePlan::new(root, old_plan));
 }
@@ -0,0 +1,247 @@
+//! Contains the [`WatchProps`] component for replanning automatically when properties change.
+
+use bevy_ecs::system::SystemParam;
+
+use crate::prelude::*;
+
+/// Automatically triggers [`UpdatePlan`] on the entity holding the [`Plan`] when one of the watched properties changes,
+/// e.g. when a `health` property set by your combat systems drops.
+///
+/// By default, changing [`Props`] never triggers a replan on its own, so that you have full control over when planning happens.
+/// Insert this component next to the [`Plan`] to opt into replanning when one of the properties read by the domain's conditions changes,
+/// or use [`WatchProps::new`] to watch an explicit list of properties instead.
+///
+/// Changes made by the [`Effect`]s applied during the execution of the plan do not trigger a replan, since the plan already anticipated them.
+/// All other changes do, including the ones made by the systems of [`Operator`]s.
+/// To not replan every frame when a property changes constantly, replans triggered by this component are debounced,
+/// see [`WatchProps::debounce`].
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_bae::prelude::*;
+/// # fn attack(_: In<OperatorInput>) -> OperatorStatus { OperatorStatus::Success }
+/// # fn flee(_: In<OperatorInput>) -> OperatorStatus { OperatorStatus::Success }
+/// fn spawn_npc(mut commands: Commands) {
+///     commands.spawn((
+///         Plan::new(),
+///         // Replans whenever `health` crosses 20
+///         WatchProps::conditions(),
+///         Select,
+///         tasks![
+///             (Operator::new(flee), conditions![Condition::lt("health", 20.0)]),
+///             Operator::new(attack),
+///         ],
+///     ));
+/// }
+/// ```
+#[derive(Component, Clone, Debug, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct WatchProps {
+    /// Which properties are watched.
+    pub source: WatchSource,
+    /// The minimum number of runs of the schedule of the [`BaePlugin`] between two replans triggered by this component.
+    /// Changes made in between are not lost, but trigger a single replan once the time is up.
+    /// Default is [`WatchProps::DEFAULT_DEBOUNCE`].
+    pub debounce: u32,
+    #[reflect(ignore)]
+    watched: Option<Vec<(Ustr, Value)>>,
+    #[reflect(ignore)]
+    pending: bool,
+    #[reflect(ignore)]
+    cooldown: u32,
+}
+
+/// Which properties are watched by [`WatchProps`].
+#[derive(Clone, Debug, PartialEq, Eq, Reflect)]
+pub enum WatchSource {
+    /// All properties read by the [`Condition`]s of the domain, i.e. the [`Conditions`] of the entity holding the [`Plan`]
+    /// and of all tasks below it, including [`UsesDomain`] and [`Include`]d tasks.
+    /// Properties read by [`Condition::new`] and [`SystemCondition`] are not known and thus not watched.
+    Conditions,
+    /// Exactly the given properties.
+    Props(Vec<Ustr>),
+}
+
+impl WatchProps {
+    /// The default value of [`WatchProps::debounce`].
+    pub const DEFAULT_DEBOUNCE: u32 = 10;
+
+    /// Watches the given properties.
+    pub fn new(props: impl IntoIterator<Item = impl Into<Ustr>>) -> Self {
+        Self::from_source(WatchSource::Props(
+            props.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    /// Watches all properties read by the [`Condition`]s of the domain. See [`WatchSource::Conditions`].
+    pub fn conditions() -> Self {
+        Self::from_source(WatchSource::Conditions)
+    }
+
+    /// Sets [`WatchProps::debounce`].
+    pub fn with_debounce(mut self, debounce: u32) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    fn from_source(source: WatchSource) -> Self {
+        Self {
+            source,
+            debounce: Self::DEFAULT_DEBOUNCE,
+            watched: None,
+            pending: false,
+            cooldown: 0,
+        }
+    }
+}
+
+/// Triggers [`UpdatePlan`] for planners whose watched properties changed since this last ran, ignoring changes made by [`Effect`]s.
+pub(crate) fn replan_on_watched_props(
+    mut planners: Query<(Entity, NameOrEntity, &mut WatchProps, &mut Props), With<Plan>>,
+    domain: DomainQuery,
+    mut commands: Commands,
+) {
+    for (entity, name, mut watch, mut props) in &mut planners {
+        let watch = &mut *watch;
+        let Some(watched) = &mut watch.watched else {
+            watch.watched = Some(domain.watched_values(entity, &watch.source, &mut props));
+            continue;
+        };
+        // Reading a missing property inserts its default value, which is not a change worth reporting
+        let props = props.bypass_change_detection();
+        for (prop, value) in watched.iter_mut() {
+            let current = *props.entry(*prop).or_default();
+            if current != *value {
+                debug!(
+                    entity=?name.entity,
+                    name=?name.name,
+                    %prop,
+                    "watched property changed"
+                );
+                *value = current;
+                watch.pending = true;
+            }
+        }
+        watch.cooldown = watch.cooldown.saturating_sub(1);
+        if watch.pending && watch.cooldown == 0 {
+            debug!(
+                entity=?name.entity,
+                name=?name.name,
+                "watched properties changed, triggering replan"
+            );
+            watch.pending = false;
+            watch.cooldown = watch.debounce;
+            commands.entity(entity).trigger(UpdatePlan::new);
+        }
+    }
+}
+
+/// Returns the watched properties of the planner that did not change since they were last checked.
+/// Pass them to [`acknowledge_watched_props`] after applying effects, so that only the changes made by the effects are ignored.
+pub(crate) fn unchanged_watched_props(world: &mut World, planner: Entity) -> Vec<Ustr> {
+    let Some(watched) = world
+        .get::<WatchProps>(planner)
+        .and_then(|watch| watch.watched.clone())
+    else {
+        return Vec::new();
+    };
+    let Some(mut props) = world.get_mut::<Props>(planner) else {
+        return Vec::new();
+    };
+    let props = props.bypass_change_detection();
+    watched
+        .into_iter()
+        .filter(|(prop, value)| *props.entry(*prop).or_default() == *value)
+        .map(|(prop, _)| prop)
+        .collect()
+}
+
+/// Takes the current values of the given watched properties as the new baseline, so that their changes are not reported.
+pub(crate) fn acknowledge_watched_props(world: &mut World, planner: Entity, unchanged: &[Ustr]) {
+    if unchanged.is_empty() {
+        return;
+    }
+    let Some(mut props) = world.get_mut::<Props>(planner) else {
+        return;
+    };
+    let props = props.bypass_change_detection();
+    let current = unchanged
+        .iter()
+        .map(|&prop| (prop, *props.entry(prop).or_default()))
+        .collect::<Vec<_>>();
+    let Some(mut watch) = world.get_mut::<WatchProps>(planner) else {
+        return;
+    };
+    let Some(watched) = &mut watch.bypass_change_detection().watched else {
+        return;
+    };
+    for (prop, value) in current {
+        if let Some((_, watched_value)) = watched.iter_mut().find(|(watched, _)| *watched == prop) {
+            *watched_value = value;
+        }
+    }
+}
+
+/// Takes the watched properties anew when a new plan was computed, since the new plan already took their current values into account
+/// and the domain may have changed, e.g. by being respawned.
+pub(crate) fn reset_watched_props(
+    replace: On<ReplacePlan>,
+    mut watches: Query<(&mut WatchProps, &mut Props)>,
+    domain: DomainQuery,
+) {
+    if let Ok((mut watch, mut props)) = watches.get_mut(replace.entity) {
+        let watched = domain.watched_values(replace.entity, &watch.source, &mut props);
+        watch.watched = Some(watched);
+    }
+}
+
+#[derive(SystemParam)]
+pub(crate) struct DomainQuery<'w, 's> {
+    uses_domain: Query<'w, 's, &'static UsesDomain>,
+    tasks: Query<'w, 's, &'static Tasks>,
+    includes: Query<'w, 's, &'static Include>,
+    condition_entities: Query<'w, 's, &'static Conditions>,
+    conditions: Query<'w, 's, &'static Condition>,
+}
+
+impl DomainQuery<'_, '_> {
+    /// Returns the watched properties of the planner together with their current values.
+    fn watched_values(
+        &self,
+        planner: Entity,
+        source: &WatchSource,
+        props: &mut Mut<Props>,
+    ) -> Vec<(Ustr, Value)> {
+        let names = match source {
+            WatchSource::Props(names) => names.clone(),
+            WatchSource::Conditions => self.condition_props(planner),
+        };
+        let props = props.bypass_change_detection();
+        names
+            .into_iter()
+            .map(|name| (name, *props.entry(name).or_default()))
+            .collect()
+    }
+
+    /// Collects the properties read by all conditions in the task hierarchy of the planner.
+    fn condition_props(&self, planner: Entity) -> Vec<Ustr> {
+        let mut props = Vec::new();
+        let root = self.uses_domain.get(planner).map_or(planner, |uses| uses.0);
+        let mut stack = vec![planner, root];
+        let mut visited = Vec::new();
+        while let Some(task) = stack.pop() {
+            if visited.contains(&task) {
+                continue;
+            }
+            visited.push(task);
+            for condition in self.condition_entities.get(task).into_iter().flatten() {
+                if let Ok(condition) = self.conditions.get(condition) {
+                    condition.read_props(&mut props);
+                }
+            }
+            stack.extend(self.tasks.get(task).into_iter().flatten());
+            stack.extend(self.includes.get(task).map(|include| include.0));
+        }
+        props
+    }
+}
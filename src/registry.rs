@@ -0,0 +1,240 @@
+//! Contains the [`BehaviorRegistry`] for referring to [`Operator`]s, [`Condition`]s and [`Effect`]s by name,
+//! and the reflectable [`NamedOperator`], [`NamedCondition`] and [`NamedEffect`] components using it.
+
+use bevy_ecs::{lifecycle::HookContext, world::DeferredWorld};
+use ustr::UstrMap;
+
+use crate::{prelude::*, task::operator::OperatorId};
+
+/// Holds operator systems, conditions and effects registered under a name with [`RegisterBehaviorExt`].
+/// Every operator system is registered exactly once, and all [`Operator`]s created from it with [`BehaviorRegistry::operator`] share it.
+#[derive(Resource, Debug, Default)]
+pub struct BehaviorRegistry {
+    operators: UstrMap<OperatorId>,
+    conditions: UstrMap<Condition>,
+    effects: UstrMap<Effect>,
+}
+
+impl BehaviorRegistry {
+    /// Returns the [`OperatorId`] registered under the given name, if any.
+    pub fn operator_id(&self, name: impl Into<Ustr>) -> Option<OperatorId> {
+        self.operators.get(&name.into()).copied()
+    }
+
+    /// Creates an [`Operator`] running the system registered under the given name, if any.
+    pub fn operator(&self, name: impl Into<Ustr>) -> Option<Operator> {
+        self.operator_id(name).map(Operator::from_system_id)
+    }
+
+    /// Returns the [`Condition`] registered under the given name, if any.
+    pub fn condition(&self, name: impl Into<Ustr>) -> Option<Condition> {
+        self.conditions.get(&name.into()).cloned()
+    }
+
+    /// Returns the [`Effect`] registered under the given name, if any.
+    pub fn effect(&self, name: impl Into<Ustr>) -> Option<Effect> {
+        self.effects.get(&name.into()).cloned()
+    }
+
+    /// Registers an already registered system under the given name, returning the [`OperatorId`] previously registered under that name.
+    /// The previous system is not unregistered, so that [`Operator`]s that already use it keep working.
+    pub fn insert_operator(
+        &mut self,
+        name: impl Into<Ustr>,
+        system_id: OperatorId,
+    ) -> Option<OperatorId> {
+        self.operators.insert(name.into(), system_id)
+    }
+
+    /// Registers a condition under the given name, returning the condition previously registered under that name.
+    pub fn insert_condition(
+        &mut self,
+        name: impl Into<Ustr>,
+        condition: Condition,
+    ) -> Option<Condition> {
+        self.conditions.insert(name.into(), condition)
+    }
+
+    /// Registers an effect under the given name, returning the effect previously registered under that name.
+    pub fn insert_effect(&mut self, name: impl Into<Ustr>, effect: Effect) -> Option<Effect> {
+        self.effects.insert(name.into(), effect)
+    }
+
+    /// Iterates over the names of all registered operators.
+    pub fn operator_names(&self) -> impl Iterator<Item = Ustr> + '_ {
+        self.operators.keys().copied()
+    }
+
+    /// Iterates over the names of all registered conditions.
+    pub fn condition_names(&self) -> impl Iterator<Item = Ustr> + '_ {
+        self.conditions.keys().copied()
+    }
+
+    /// Iterates over the names of all registered effects.
+    pub fn effect_names(&self) -> impl Iterator<Item = Ustr> + '_ {
+        self.effects.keys().copied()
+    }
+}
+
+/// Extension trait for registering behaviors under a name. See [`BehaviorRegistry`].
+pub trait RegisterBehaviorExt {
+    /// Registers the given system in the [`BehaviorRegistry`] under the given name.
+    /// The system must take [`OperatorInput`] as input and return an [`OperatorStatus`], just like with [`Operator::new`].
+    /// Registering another system under the same name replaces it for all [`Operator`]s created afterwards.
+    fn register_operator<S, M>(&mut self, name: impl Into<Ustr>, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<OperatorInput>, OperatorStatus, M> + 'static;
+
+    /// Registers the given condition in the [`BehaviorRegistry`] under the given name.
+    /// Registering another condition under the same name replaces it for all [`NamedCondition`]s inserted afterwards.
+    fn register_condition(&mut self, name: impl Into<Ustr>, condition: Condition) -> &mut Self;
+
+    /// Registers the given effect in the [`BehaviorRegistry`] under the given name.
+    /// Registering another effect under the same name replaces it for all [`NamedEffect`]s inserted afterwards.
+    fn register_effect(&mut self, name: impl Into<Ustr>, effect: Effect) -> &mut Self;
+}
+
+impl RegisterBehaviorExt for World {
+    fn register_operator<S, M>(&mut self, name: impl Into<Ustr>, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<OperatorInput>, OperatorStatus, M> + 'static,
+    {
+        let system_id = self.register_system(system);
+        self.get_resource_or_init::<BehaviorRegistry>()
+            .insert_operator(name, system_id);
+        self
+    }
+
+    fn register_condition(&mut self, name: impl Into<Ustr>, condition: Condition) -> &mut Self {
+        self.get_resource_or_init::<BehaviorRegistry>()
+            .insert_condition(name, condition);
+        self
+    }
+
+    fn register_effect(&mut self, name: impl Into<Ustr>, effect: Effect) -> &mut Self {
+        self.get_resource_or_init::<BehaviorRegistry>()
+            .insert_effect(name, effect);
+        self
+    }
+}
+
+impl RegisterBehaviorExt for App {
+    fn register_operator<S, M>(&mut self, name: impl Into<Ustr>, system: S) -> &mut Self
+    where
+        S: IntoSystem<In<OperatorInput>, OperatorStatus, M> + 'static,
+    {
+        self.world_mut().register_operator(name, system);
+        self
+    }
+
+    fn register_condition(&mut self, name: impl Into<Ustr>, condition: Condition) -> &mut Self {
+        self.world_mut().register_condition(name, condition);
+        self
+    }
+
+    fn register_effect(&mut self, name: impl Into<Ustr>, effect: Effect) -> &mut Self {
+        self.world_mut().register_effect(name, effect);
+        self
+    }
+}
+
+/// Inserts the [`Operator`] registered under this name with [`RegisterBehaviorExt::register_operator`].
+/// Unlike an [`Operator`], this component can be reflected, and thus be saved in scenes or sent over the network.
+///
+/// The name is resolved once, when this component is inserted. Logs an error if no operator was registered under the name.
+#[derive(Component, Clone, Debug, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component, Clone, Debug, PartialEq, Hash)]
+#[component(on_insert = Self::on_insert_hook)]
+pub struct NamedOperator(pub String);
+
+impl NamedOperator {
+    /// Creates a new [`NamedOperator`] referring to the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    fn on_insert_hook(mut world: DeferredWorld, context: HookContext) {
+        let name = Ustr::from(world.get::<Self>(context.entity).unwrap().0.as_str());
+        let operator = world
+            .get_resource::<BehaviorRegistry>()
+            .and_then(|registry| registry.operator(name));
+        let Some(operator) = operator else {
+            tracing::error!(entity=?context.entity, %name, "no operator is registered under this name");
+            return;
+        };
+        world.commands().entity(context.entity).insert(operator);
+    }
+}
+
+/// Inserts the [`Condition`] registered under this name with [`RegisterBehaviorExt::register_condition`].
+/// Unlike a [`Condition`], this component can be reflected, and thus be saved in scenes or sent over the network.
+///
+/// The name is resolved once, when this component is inserted. Logs an error if no condition was registered under the name.
+#[derive(Component, Clone, Debug, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component, Clone, Debug, PartialEq, Hash)]
+#[component(on_insert = Self::on_insert_hook)]
+pub struct NamedCondition(pub String);
+
+impl NamedCondition {
+    /// Creates a new [`NamedCondition`] referring to the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    fn on_insert_hook(mut world: DeferredWorld, context: HookContext) {
+        let name = Ustr::from(world.get::<Self>(context.entity).unwrap().0.as_str());
+        let condition = world
+            .get_resource::<BehaviorRegistry>()
+            .and_then(|registry| registry.condition(name));
+        let Some(condition) = condition else {
+            tracing::error!(entity=?context.entity, %name, "no condition is registered under this name");
+            return;
+        };
+        world.commands().entity(context.entity).insert(condition);
+    }
+}
+
+/// Inserts the [`Effect`] registered under this name with [`RegisterBehaviorExt::register_effect`].
+/// Unlike an [`Effect`], this component can be reflected, and thus be saved in scenes or sent over the network.
+///
+/// The name is resolved once, when this component is inserted. Logs an error if no effect was registered under the name.
+#[derive(Component, Clone, Debug, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component, Clone, Debug, PartialEq, Hash)]
+#[component(on_insert = Self::on_insert_hook)]
+pub struct NamedEffect {
+    /// The name the effect was registered under.
+    pub name: String,
+    /// Overrides [`Effect::plan_only`] of the registered effect if `true`.
+    pub plan_only: bool,
+}
+
+impl NamedEffect {
+    /// Creates a new [`NamedEffect`] referring to the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            plan_only: false,
+        }
+    }
+
+    /// Sets [`NamedEffect::plan_only`]. See [`Effect::plan_only`].
+    pub fn plan_only(mut self) -> Self {
+        self.plan_only = true;
+        self
+    }
+
+    fn on_insert_hook(mut world: DeferredWorld, context: HookContext) {
+        let named = world.get::<Self>(context.entity).unwrap();
+        let name = Ustr::from(named.name.as_str());
+        let plan_only = named.plan_only;
+        let effect = world
+            .get_resource::<BehaviorRegistry>()
+            .and_then(|registry| registry.effect(name));
+        let Some(mut effect) = effect else {
+            tracing::error!(entity=?context.entity, %name, "no effect is registered under this name");
+            return;
+        };
+        effect.plan_only |= plan_only;
+        world.commands().entity(context.entity).insert(effect);
+    }
+}
@@ -0,0 +1,115 @@
+//! Contains the [`Include`] [`CompoundTask`]
+
+use crate::{
+    prelude::*,
+    task::compound::{
+        DecomposeId, DecomposeInput, DecomposeResult, Subtask, SubtaskQuery, decompose_subtask,
+    },
+};
+
+/// A [`CompoundTask`] that decomposes another task in its place, as if that task was its only subtask.
+/// Use this to reuse a subtree in several places without spawning it multiple times.
+/// The included task can be any [`Operator`] or [`CompoundTask`], and its own [`Conditions`] and [`Effects`] apply as usual.
+/// It does not need to be part of a planned hierarchy itself, so you can keep a library of subtrees on entities without a [`Plan`].
+///
+/// Including a task that is already being decomposed, i.e. a task that directly or through other [`Include`]s contains this one,
+/// would recurse forever. Such a decomposition fails and logs an error instead.
+///
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_bae::prelude::*;
+/// # fn run_to_cover(_: In<OperatorInput>) -> OperatorStatus { OperatorStatus::Success }
+/// # fn hide(_: In<OperatorInput>) -> OperatorStatus { OperatorStatus::Success }
+/// # fn attack(_: In<OperatorInput>) -> OperatorStatus { OperatorStatus::Success }
+/// fn spawn_npc(mut commands: Commands) {
+///     let flee = commands
+///         .spawn((
+///             Sequence,
+///             tasks![Operator::new(run_to_cover), Operator::new(hide)],
+///         ))
+///         .id();
+///     commands.spawn((
+///         Plan::new(),
+///         Select,
+///         tasks![
+///             (Include(flee), conditions![Condition::lt("health", 20.0)]),
+///             Operator::new(attack),
+///             Include(flee),
+///         ],
+///     ));
+/// }
+/// ```
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Include(#[entities] pub Entity);
+
+impl CompoundTask for Include {
+    fn register_decompose(commands: &mut Commands) -> DecomposeId {
+        commands.register_system(decompose_include)
+    }
+}
+
+/// The [`Include`]s that are currently being decomposed, outermost first.
+#[derive(Resource, Debug, Default)]
+struct IncludeStack(Vec<Entity>);
+
+fn decompose_include(
+    In(ctx): In<DecomposeInput>,
+    world: &mut World,
+    mut individual_tasks: Local<SubtaskQuery>,
+    mut conditions: Local<QueryState<(Entity, &Condition)>>,
+    mut effects: Local<QueryState<(Entity, &Effect)>>,
+) -> DecomposeResult {
+    let include = ctx.compound_task;
+    let Some(&Include(target)) = world.get::<Include>(include) else {
+        return DecomposeResult::Failure;
+    };
+    let Ok((entity, has_operator, compound_task, task_conditions, task_effects)) =
+        individual_tasks.get(world, target)
+    else {
+        debug!(?include, ?target, "included entity is not a task");
+        return DecomposeResult::Failure;
+    };
+    let subtask = Subtask {
+        entity,
+        has_operator,
+        compound_task: compound_task.cloned(),
+        conditions: task_conditions.cloned(),
+        effects: task_effects.cloned(),
+    };
+
+    world.init_resource::<IncludeStack>();
+    // Every task that is currently being decomposed is the root, one of the includes, or one of their ancestors
+    let is_cycle = world
+        .resource::<IncludeStack>()
+        .0
+        .iter()
+        .chain([&include])
+        .any(|&include| is_self_or_ancestor(world, target, include));
+    if is_cycle {
+        tracing::error!(
+            ?include,
+            ?target,
+            "included task contains this include, which would recurse forever"
+        );
+        return DecomposeResult::Failure;
+    }
+
+    world.resource_mut::<IncludeStack>().0.push(include);
+    let result = decompose_subtask(world, &mut conditions, &mut effects, &subtask, ctx);
+    world.resource_mut::<IncludeStack>().0.pop();
+    result
+}
+
+/// Walks up the [`TaskOf`] relationships from `task` and checks whether `candidate` is among them.
+fn is_self_or_ancestor(world: &World, candidate: Entity, mut task: Entity) -> bool {
+    loop {
+        if task == candidate {
+            return true;
+        }
+        match world.get::<TaskOf>(task) {
+            Some(parent) => task = parent.0,
+            None => return false,
+        }
+    }
+}
@@ -1,17 +1,23 @@
 //! Contains types representing a tree of more compound tasks, where the leaves are [`Operator`]s
 
-use bevy_ecs::system::SystemId;
+use bevy_ecs::{entity_disabling::Disabled, system::SystemId};
 
 use crate::{
-    plan::{Plan, mtr::Mtr},
+    condition::system::check_planning_condition,
+    plan::{Plan, PlannedOperator, mtr::Mtr, trace},
     prelude::*,
 };
 
+pub mod include;
+pub mod parallel;
+pub mod random_select;
 pub mod relationship;
+pub mod repeat;
 pub mod select;
 pub mod sequence;
+pub mod utility_select;
 
-/// Trait implemented for compound tasks. The builtin [`CompoundTask`]s are [`Sequence`] and [`Select`].
+/// Trait implemented for compound tasks. The builtin [`CompoundTask`]s are [`Sequence`], [`Select`], [`UtilitySelect`], [`RandomSelect`], [`Repeat`], [`Parallel`] and [`Include`].
 /// If you implement this trait, you must also call [`CompoundAppExt::add_compound_task`] to initialize it.
 pub trait CompoundTask: Component {
     /// Registers the decomposition system for this compound task.
@@ -40,6 +46,10 @@ pub struct DecomposeInput {
     /// The running conditions that must be met to event enter this decomposition.
     /// Make sure to add these to the first operator of the decomposition so they're validated at runtime.
     pub conditions: Vec<Entity>,
+    /// The number of valid decompositions to skip before returning one.
+    /// This is `0` for regular planning, and is increased by backtracking compound tasks like a [`Sequence`] with [`Backtrack`]
+    /// in order to ask for the next alternative. Return [`DecomposeResult::Failure`] if there are no alternatives left.
+    pub skip: usize,
 }
 
 #[derive(Component, Clone)]
@@ -70,6 +80,227 @@ pub enum DecomposeResult {
     Failure,
 }
 
+/// A subtask of a [`CompoundTask`], cloned out of the [`World`] so that it can be decomposed while the world is being mutated.
+#[derive(Clone)]
+pub(crate) struct Subtask {
+    pub(crate) entity: Entity,
+    pub(crate) has_operator: bool,
+    pub(crate) compound_task: Option<TypeErasedCompoundTask>,
+    pub(crate) conditions: Option<Conditions>,
+    pub(crate) effects: Option<Effects>,
+}
+
+/// The [`QueryState`] used to fetch [`Subtask`]s.
+pub(crate) type SubtaskQuery = QueryState<
+    (
+        Entity,
+        Has<Operator>,
+        Option<&'static TypeErasedCompoundTask>,
+        Option<&'static Conditions>,
+        Option<&'static Effects>,
+    ),
+    Or<(With<Operator>, With<TypeErasedCompoundTask>)>,
+>;
+
+impl Subtask {
+    /// Reads the subtask from the given entity. Returns `None` if it is neither an [`Operator`] nor a [`CompoundTask`], or is disabled.
+    pub(crate) fn read(world: &World, entity: Entity) -> Option<Self> {
+        let entity_ref = world.get_entity(entity).ok()?;
+        let has_operator = entity_ref.contains::<Operator>();
+        let compound_task = entity_ref.get::<TypeErasedCompoundTask>().cloned();
+        if entity_ref.contains::<Disabled>() || (!has_operator && compound_task.is_none()) {
+            return None;
+        }
+        Some(Self {
+            entity,
+            has_operator,
+            compound_task,
+            conditions: entity_ref.get::<Conditions>().cloned(),
+            effects: entity_ref.get::<Effects>().cloned(),
+        })
+    }
+
+    /// Collects the subtasks of the given compound task in order. Returns `None` if the entity has no [`Tasks`].
+    pub(crate) fn collect(
+        world: &World,
+        task_relations: &mut QueryState<&Tasks>,
+        subtasks: &mut SubtaskQuery,
+        compound_task: Entity,
+    ) -> Option<Vec<Self>> {
+        let tasks = task_relations.get(world, compound_task).ok()?;
+        Some(
+            subtasks
+                .iter_many(world, tasks)
+                .map(
+                    |(entity, has_operator, compound_task, conditions, effects)| Self {
+                        entity,
+                        has_operator,
+                        compound_task: compound_task.cloned(),
+                        conditions: conditions.cloned(),
+                        effects: effects.cloned(),
+                    },
+                )
+                .collect(),
+        )
+    }
+}
+
+/// Decomposes single subtasks for the decomposition logic shared between [`CompoundTask`]s,
+/// so that it can run both with exclusive access to the [`World`] and read-only during batch planning.
+pub(crate) trait Decomposer {
+    /// Decomposes a single subtask. See [`decompose_subtask`].
+    fn decompose_subtask(&mut self, subtask: &Subtask, ctx: DecomposeInput) -> DecomposeResult;
+
+    /// Called when the subtask is not tried because its [`Mtr`] has a lower priority than the running plan.
+    fn reject(&mut self, _subtask: &Subtask, _mtr: &Mtr, _previous_mtr: &Mtr) {}
+}
+
+/// The regular [`Decomposer`], which runs the decomposition systems of [`CompoundTask`]s.
+pub(crate) struct ExclusiveDecomposer<'a> {
+    pub(crate) world: &'a mut World,
+    pub(crate) conditions: &'a mut QueryState<(Entity, &'static Condition)>,
+    pub(crate) effects: &'a mut QueryState<(Entity, &'static Effect)>,
+}
+
+impl Decomposer for ExclusiveDecomposer<'_> {
+    fn decompose_subtask(&mut self, subtask: &Subtask, ctx: DecomposeInput) -> DecomposeResult {
+        decompose_subtask(self.world, self.conditions, self.effects, subtask, ctx)
+    }
+
+    fn reject(&mut self, subtask: &Subtask, mtr: &Mtr, previous_mtr: &Mtr) {
+        trace::reject(self.world, subtask.entity, mtr, previous_mtr);
+    }
+}
+
+/// Decomposes a single subtask: checks its conditions, appends it to the plan if it is an [`Operator`] or runs the decomposition of its [`CompoundTask`],
+/// and finally applies its effects.
+/// [`DecomposeInput::conditions`] holds the conditions inherited from the parent and will be extended by the conditions of the subtask.
+/// [`Operator`]s can only be decomposed in a single way, so they fail if [`DecomposeInput::skip`] is not `0`.
+pub(crate) fn decompose_subtask(
+    world: &mut World,
+    conditions: &mut QueryState<(Entity, &Condition)>,
+    effects: &mut QueryState<(Entity, &Effect)>,
+    subtask: &Subtask,
+    ctx: DecomposeInput,
+) -> DecomposeResult {
+    trace::enter(world, subtask.entity, ctx.skip);
+    let result = try_decompose_subtask(world, conditions, effects, subtask, ctx);
+    trace::exit(world, &result);
+    result
+}
+
+fn try_decompose_subtask(
+    world: &mut World,
+    conditions: &mut QueryState<(Entity, &Condition)>,
+    effects: &mut QueryState<(Entity, &Effect)>,
+    subtask: &Subtask,
+    mut ctx: DecomposeInput,
+) -> DecomposeResult {
+    if let Some(condition_relations) = &subtask.conditions {
+        for entity in condition_relations.iter() {
+            match check_planning_condition(
+                world,
+                conditions,
+                &mut ctx.world_state,
+                ctx.planner,
+                entity,
+            ) {
+                Some(true) => ctx.conditions.push(entity),
+                Some(false) => return DecomposeResult::Failure,
+                None => {}
+            }
+        }
+    }
+    let (mut plan, mut world_state) = if subtask.has_operator {
+        if ctx.skip > 0 {
+            return DecomposeResult::Failure;
+        }
+        ctx.plan.push_back(PlannedOperator {
+            entity: subtask.entity,
+            effects: vec![],
+            conditions: ctx.conditions,
+            parallel: None,
+            started: false,
+        });
+        (ctx.plan, ctx.world_state)
+    } else if let Some(compound_task) = &subtask.compound_task {
+        let result = world.run_system_with(
+            compound_task.decompose,
+            DecomposeInput {
+                compound_task: subtask.entity,
+                ..ctx
+            },
+        );
+        world.flush();
+        match result {
+            Ok(DecomposeResult::Success { plan, world_state }) => (plan, world_state),
+            Ok(DecomposeResult::Rejection) => return DecomposeResult::Rejection,
+            Ok(DecomposeResult::Failure) | Err(_) => return DecomposeResult::Failure,
+        }
+    } else {
+        unreachable!()
+    };
+    if plan.is_empty() {
+        return DecomposeResult::Failure;
+    }
+    if let Some(effect_relations) = &subtask.effects {
+        for (entity, effect) in effects.iter_many(world, effect_relations.iter()) {
+            effect.apply(&mut world_state);
+            plan.back_mut().unwrap().effects.push(entity);
+        }
+    }
+    DecomposeResult::Success { plan, world_state }
+}
+
+/// Decomposes into the first valid subtask in the given order, which is what [`Select`] and its variants do.
+/// The position of the chosen subtask within `subtasks` is recorded in the [`Mtr`], so the order defines the priority of the subtasks.
+/// Every valid decomposition of every subtask counts as one alternative for [`DecomposeInput::skip`].
+pub(crate) fn decompose_first_valid<'a>(
+    decomposer: &mut impl Decomposer,
+    ctx: DecomposeInput,
+    subtasks: impl IntoIterator<Item = &'a Subtask>,
+) -> DecomposeResult {
+    let mut skip = ctx.skip;
+    for (i, subtask) in subtasks.into_iter().enumerate() {
+        let mtr = ctx.plan.mtr.clone().with(i as u16);
+        if mtr > ctx.previous_mtr {
+            decomposer.reject(subtask, &mtr, &ctx.previous_mtr);
+            return DecomposeResult::Rejection;
+        }
+        for alternative in 0.. {
+            let result = decomposer.decompose_subtask(
+                subtask,
+                DecomposeInput {
+                    planner: ctx.planner,
+                    compound_task: subtask.entity,
+                    world_state: ctx.world_state.clone(),
+                    plan: ctx.plan.clone(),
+                    previous_mtr: ctx.previous_mtr.clone(),
+                    conditions: ctx.conditions.clone(),
+                    skip: alternative,
+                },
+            );
+            match result {
+                DecomposeResult::Success {
+                    mut plan,
+                    world_state,
+                } => {
+                    if skip > 0 {
+                        skip -= 1;
+                        continue;
+                    }
+                    // only use the first match
+                    plan.mtr.push(i as u16);
+                    return DecomposeResult::Success { plan, world_state };
+                }
+                DecomposeResult::Rejection => return DecomposeResult::Rejection,
+                DecomposeResult::Failure => break,
+            }
+        }
+    }
+    DecomposeResult::Failure
+}
+
 /// Used to allow calling [`CompoundAppExt::add_compound_task`] on [`App`].
 pub trait CompoundAppExt {
     /// Registers a new [`CompoundTask`] with the [`App`].
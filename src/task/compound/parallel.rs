@@ -0,0 +1,125 @@
+//! Contains the [`Parallel`] [`CompoundTask`]
+
+use crate::{
+    plan::{Plan, PlannedBranch, PlannedOperator, PlannedParallel},
+    prelude::*,
+    task::compound::{
+        DecomposeId, DecomposeInput, DecomposeResult, Subtask, SubtaskQuery, decompose_subtask,
+    },
+};
+
+/// A [`CompoundTask`] that decomposes all subtasks, given that they are all valid, and executes them concurrently.
+/// Each subtask becomes its own branch within a single step of the [`Plan`]. The step is done once its [`ParallelPolicy`] says so,
+/// and a failing step aborts the plan like a failing [`Operator`] does.
+///
+/// During planning, the subtasks are decomposed in order, so the effects of earlier subtasks are visible to the conditions of later ones.
+/// The effects of the [`Parallel`] task itself are applied when the whole step is done.
+#[derive(Debug, Component, Default, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Parallel {
+    /// The policy deciding when the concurrently executing branches are done.
+    pub policy: ParallelPolicy,
+}
+
+impl Parallel {
+    /// Creates a new [`Parallel`] task with the given policy.
+    pub fn new(policy: ParallelPolicy) -> Self {
+        Self { policy }
+    }
+}
+
+/// Decides when a [`Parallel`] task is done executing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum ParallelPolicy {
+    /// Done once all branches succeeded. Aborts the plan as soon as any branch fails.
+    #[default]
+    AllSucceed,
+    /// Done as soon as any branch succeeded. The other branches are not run anymore. Aborts the plan once all branches failed.
+    AnySucceeds,
+    /// Done as soon as the first branch finished. The other branches are not run anymore. Aborts the plan if that branch failed.
+    FirstFinished,
+}
+
+impl CompoundTask for Parallel {
+    fn register_decompose(commands: &mut Commands) -> DecomposeId {
+        commands.register_system(decompose_parallel)
+    }
+}
+
+fn decompose_parallel(
+    In(mut ctx): In<DecomposeInput>,
+    world: &mut World,
+    mut task_relations: Local<QueryState<&Tasks>>,
+    mut individual_tasks: Local<SubtaskQuery>,
+    mut conditions: Local<QueryState<(Entity, &Condition)>>,
+    mut effects: Local<QueryState<(Entity, &Effect)>>,
+) -> DecomposeResult {
+    let Some(subtasks) = Subtask::collect(
+        world,
+        &mut task_relations,
+        &mut individual_tasks,
+        ctx.compound_task,
+    ) else {
+        return DecomposeResult::Failure;
+    };
+    // Like a greedy sequence, a parallel task only has a single decomposition
+    if subtasks.is_empty() || ctx.skip > 0 {
+        return DecomposeResult::Failure;
+    }
+    let Some(&Parallel { policy }) = world.get::<Parallel>(ctx.compound_task) else {
+        return DecomposeResult::Failure;
+    };
+
+    let mut world_state = ctx.world_state;
+    let mut mtr = ctx.plan.mtr.clone();
+    let mut branches = Vec::with_capacity(subtasks.len());
+    for subtask in &subtasks {
+        let result = decompose_subtask(
+            world,
+            &mut conditions,
+            &mut effects,
+            subtask,
+            DecomposeInput {
+                planner: ctx.planner,
+                compound_task: subtask.entity,
+                world_state,
+                plan: Plan {
+                    mtr,
+                    ..Plan::default()
+                },
+                previous_mtr: ctx.previous_mtr.clone(),
+                // Our own conditions are checked for the whole step, so the branches don't need to inherit them
+                conditions: Vec::new(),
+                skip: 0,
+            },
+        );
+        match result {
+            DecomposeResult::Success {
+                plan,
+                world_state: new_world_state,
+            } => {
+                branches.push(PlannedBranch {
+                    operators_left: plan.operators_left,
+                    status: OperatorStatus::Ongoing,
+                });
+                mtr = plan.mtr;
+                world_state = new_world_state;
+            }
+            DecomposeResult::Rejection => return DecomposeResult::Rejection,
+            DecomposeResult::Failure => return DecomposeResult::Failure,
+        }
+    }
+
+    ctx.plan.mtr = mtr;
+    ctx.plan.push_back(PlannedOperator {
+        entity: ctx.compound_task,
+        effects: vec![],
+        conditions: ctx.conditions,
+        parallel: Some(PlannedParallel { policy, branches }),
+        started: false,
+    });
+    DecomposeResult::Success {
+        plan: ctx.plan,
+        world_state,
+    }
+}
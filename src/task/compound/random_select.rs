@@ -0,0 +1,149 @@
+//! Contains the [`RandomSelect`] [`CompoundTask`] and the [`RandomSeed`] used to make it reproducible.
+
+use bevy_ecs::{lifecycle::HookContext, world::DeferredWorld};
+
+use crate::{
+    prelude::*,
+    task::compound::{
+        DecomposeId, DecomposeInput, DecomposeResult, ExclusiveDecomposer, Subtask, SubtaskQuery,
+        decompose_first_valid,
+    },
+};
+
+/// A [`CompoundTask`] that decomposes into a random valid subtask.
+/// The chance of a valid subtask to be picked is proportional to its [`RandomWeight`], and subtasks without a [`RandomWeight`] have a weight of `1.0`.
+/// Subtasks with a weight of zero or less are never picked.
+///
+/// The randomness is fully determined by the [`RandomSeed`] of the entity holding the [`Plan`] and the position of the task in its task hierarchy,
+/// so the same seed reproduces the same choices across runs and scene loads.
+/// This also means that replanning while a plan is running will make the same choices, so the [`Mtr`](crate::plan::mtr::Mtr)-based interruption logic keeps working as if the subtasks were declared in the randomly chosen order.
+#[derive(Debug, Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct RandomSelect;
+
+impl CompoundTask for RandomSelect {
+    fn register_decompose(commands: &mut Commands) -> DecomposeId {
+        commands.register_system(decompose_random_select)
+    }
+}
+
+/// The weight of a subtask of a [`RandomSelect`]. Higher weights are picked more often.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct RandomWeight(pub f32);
+
+impl Default for RandomWeight {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// The seed used by all [`RandomSelect`]s when planning for the entity holding this component. Automatically added to entities holding a [`Plan`].
+/// Set this to a fixed value to get reproducible plans.
+///
+/// A seed of `0`, which is also the default, is replaced with one derived from the entity when the component is added,
+/// so that a crowd of agents sharing a domain doesn't make the same choices in lockstep.
+/// The replaced seed is stored in the component, so saving and loading it in a scene still reproduces the same plans.
+///
+/// The seed is advanced every time a new plan is computed for an empty [`Plan`], so that subsequent plans can pick different subtasks.
+/// Updating a [`Plan`] that is still running uses the same seed that was used to create it.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Reflect, Deref, DerefMut)]
+#[reflect(Component)]
+#[component(on_add = Self::on_add_hook)]
+pub struct RandomSeed(pub u64);
+
+impl RandomSeed {
+    /// Creates a new seed.
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Moves on to the next seed.
+    pub fn advance(&mut self) {
+        self.0 = splitmix64(&mut self.0);
+    }
+
+    fn on_add_hook(mut world: DeferredWorld, context: HookContext) {
+        let Some(mut seed) = world.get_mut::<Self>(context.entity) else {
+            return;
+        };
+        if seed.0 == 0 {
+            seed.0 = splitmix64(&mut context.entity.to_bits());
+        }
+    }
+}
+
+/// A small, fast and deterministic PRNG. See <https://prng.di.unimi.it/splitmix64.c>
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Hashes the indices of the task and all of its ancestors within their parent's [`Tasks`].
+fn hierarchy_position(world: &World, mut task: Entity) -> u64 {
+    let mut hash = 0;
+    while let Some(&TaskOf(parent)) = world.get::<TaskOf>(task) {
+        let index = world
+            .get::<Tasks>(parent)
+            .and_then(|tasks| tasks.iter().position(|&subtask| subtask == task))
+            .unwrap_or_default();
+        hash = splitmix64(&mut hash) ^ index as u64;
+        task = parent;
+    }
+    hash
+}
+
+fn decompose_random_select(
+    In(ctx): In<DecomposeInput>,
+    world: &mut World,
+    mut task_relations: Local<QueryState<&Tasks>>,
+    mut individual_tasks: Local<SubtaskQuery>,
+    mut weights: Local<QueryState<&RandomWeight>>,
+    mut conditions: Local<QueryState<(Entity, &Condition)>>,
+    mut effects: Local<QueryState<(Entity, &Effect)>>,
+) -> DecomposeResult {
+    let Some(subtasks) = Subtask::collect(
+        world,
+        &mut task_relations,
+        &mut individual_tasks,
+        ctx.compound_task,
+    ) else {
+        return DecomposeResult::Failure;
+    };
+
+    // Every random select in the domain gets its own stream of random numbers.
+    // Entities differ between runs and scene loads, so the stream is keyed by the position of the task in the hierarchy instead.
+    let mut state = world
+        .get::<RandomSeed>(ctx.planner)
+        .map_or(0, |seed| seed.0);
+    state = splitmix64(&mut state) ^ hierarchy_position(world, ctx.compound_task);
+
+    // Weighted random sampling without replacement (Efraimidis and Spirakis),
+    // so the first valid subtask in this order is picked with a chance proportional to its weight among all valid subtasks.
+    let mut keyed = Vec::with_capacity(subtasks.len());
+    for subtask in &subtasks {
+        let weight = weights
+            .get(world, subtask.entity)
+            .map_or(1.0, |weight| weight.0);
+        // Always roll so that the keys don't depend on the weights of previous subtasks
+        let roll = (splitmix64(&mut state) >> 11) as f64 / (1_u64 << 53) as f64;
+        if weight <= 0.0 {
+            continue;
+        }
+        keyed.push((roll.ln() / weight as f64, subtask));
+    }
+    keyed.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    let mut decomposer = ExclusiveDecomposer {
+        world,
+        conditions: &mut conditions,
+        effects: &mut effects,
+    };
+    decompose_first_valid(
+        &mut decomposer,
+        ctx,
+        keyed.into_iter().map(|(_, subtask)| subtask),
+    )
+}
@@ -0,0 +1,167 @@
+//! Contains the [`Repeat`] [`CompoundTask`]
+
+use crate::{
+    prelude::*,
+    task::compound::{
+        DecomposeId, DecomposeInput, DecomposeResult, Subtask, SubtaskQuery, decompose_subtask,
+    },
+};
+
+/// A [`CompoundTask`] that decomposes all subtasks like a [`Sequence`], and then does so again as configured by its [`RepeatMode`].
+/// Every repetition is planned with the effects of the previous ones applied, so conditions of the subtasks are re-evaluated each time.
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct Repeat {
+    /// How often the subtasks are repeated.
+    pub mode: RepeatMode,
+    /// Hard upper limit for the number of repetitions, which prevents infinite decompositions.
+    /// A [`RepeatMode::Times`] exceeding this limit fails instead of planning fewer repetitions.
+    /// Defaults to [`Repeat::DEFAULT_MAX_ITERATIONS`].
+    pub max_iterations: u32,
+}
+
+/// Decides how often a [`Repeat`] task repeats its subtasks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum RepeatMode {
+    /// Repeat the subtasks exactly this many times. Fails if any repetition is not valid, or if this exceeds [`Repeat::max_iterations`].
+    Times(u32),
+    /// Repeat the subtasks for as long as they are valid. Fails if not even the first repetition is valid.
+    WhileValid,
+}
+
+impl Repeat {
+    /// The default value of [`Repeat::max_iterations`].
+    pub const DEFAULT_MAX_ITERATIONS: u32 = 64;
+
+    /// Creates a [`Repeat`] that repeats its subtasks exactly `count` times.
+    pub fn times(count: u32) -> Self {
+        Self {
+            mode: RepeatMode::Times(count),
+            max_iterations: Self::DEFAULT_MAX_ITERATIONS,
+        }
+    }
+
+    /// Creates a [`Repeat`] that repeats its subtasks for as long as they are valid.
+    pub fn while_valid() -> Self {
+        Self {
+            mode: RepeatMode::WhileValid,
+            max_iterations: Self::DEFAULT_MAX_ITERATIONS,
+        }
+    }
+
+    /// Sets [`Repeat::max_iterations`].
+    pub fn with_max_iterations(mut self, max_iterations: u32) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+}
+
+impl Default for Repeat {
+    fn default() -> Self {
+        Self::while_valid()
+    }
+}
+
+impl CompoundTask for Repeat {
+    fn register_decompose(commands: &mut Commands) -> DecomposeId {
+        commands.register_system(decompose_repeat)
+    }
+}
+
+fn decompose_repeat(
+    In(ctx): In<DecomposeInput>,
+    world: &mut World,
+    mut task_relations: Local<QueryState<&Tasks>>,
+    mut individual_tasks: Local<SubtaskQuery>,
+    mut conditions: Local<QueryState<(Entity, &Condition)>>,
+    mut effects: Local<QueryState<(Entity, &Effect)>>,
+) -> DecomposeResult {
+    let Some(subtasks) = Subtask::collect(
+        world,
+        &mut task_relations,
+        &mut individual_tasks,
+        ctx.compound_task,
+    ) else {
+        return DecomposeResult::Failure;
+    };
+    // Like a greedy sequence, a repeat only has a single decomposition
+    if subtasks.is_empty() || ctx.skip > 0 {
+        return DecomposeResult::Failure;
+    }
+    let Some(&repeat) = world.get::<Repeat>(ctx.compound_task) else {
+        return DecomposeResult::Failure;
+    };
+    let iterations = match repeat.mode {
+        RepeatMode::Times(count) if count > repeat.max_iterations => {
+            tracing::warn!(
+                task=?ctx.compound_task,
+                count,
+                max_iterations = repeat.max_iterations,
+                "`Repeat` is configured to repeat more often than its `max_iterations` allow, so it cannot be decomposed"
+            );
+            return DecomposeResult::Failure;
+        }
+        RepeatMode::Times(count) => count,
+        RepeatMode::WhileValid => repeat.max_iterations,
+    };
+
+    let mut plan = ctx.plan;
+    let mut world_state = ctx.world_state;
+    let mut inherited_conditions = Some(ctx.conditions);
+    let mut completed_iterations = 0;
+    'iteration: for _ in 0..iterations {
+        // Roll back to this state if the iteration turns out to be invalid
+        let mut iteration_plan = plan.clone();
+        let mut iteration_world_state = world_state.clone();
+        for (i, subtask) in subtasks.iter().enumerate() {
+            let result = decompose_subtask(
+                world,
+                &mut conditions,
+                &mut effects,
+                subtask,
+                DecomposeInput {
+                    planner: ctx.planner,
+                    compound_task: subtask.entity,
+                    world_state: iteration_world_state,
+                    plan: iteration_plan,
+                    previous_mtr: ctx.previous_mtr.clone(),
+                    // Only the very first "entry" subtask needs to inherit our conditions
+                    conditions: if i == 0 {
+                        inherited_conditions.take().unwrap_or_default()
+                    } else {
+                        Vec::new()
+                    },
+                    skip: 0,
+                },
+            );
+            match result {
+                DecomposeResult::Success {
+                    plan: new_plan,
+                    world_state: new_world_state,
+                } => {
+                    iteration_plan = new_plan;
+                    iteration_world_state = new_world_state;
+                }
+                DecomposeResult::Rejection => return DecomposeResult::Rejection,
+                DecomposeResult::Failure => match repeat.mode {
+                    RepeatMode::Times(_) => return DecomposeResult::Failure,
+                    RepeatMode::WhileValid => break 'iteration,
+                },
+            }
+        }
+        plan = iteration_plan;
+        world_state = iteration_world_state;
+        completed_iterations += 1;
+    }
+
+    if completed_iterations == 0 {
+        return DecomposeResult::Failure;
+    }
+    if repeat.mode == RepeatMode::WhileValid && completed_iterations == repeat.max_iterations {
+        debug!(
+            compound_task=?ctx.compound_task,
+            "reached the maximum number of iterations of a repeat, stopping decomposition"
+        );
+    }
+    DecomposeResult::Success { plan, world_state }
+}
@@ -1,12 +1,16 @@
 //! Contains the [`Sequence`] [`CompoundTask`]
 
 use crate::{
-    plan::PlannedOperator,
+    plan::Plan,
     prelude::*,
-    task::compound::{DecomposeId, DecomposeInput, DecomposeResult, TypeErasedCompoundTask},
+    task::compound::{
+        DecomposeId, DecomposeInput, DecomposeResult, Decomposer, ExclusiveDecomposer, Subtask,
+        SubtaskQuery,
+    },
 };
 
 /// A [`CompoundTask`] that decomposes into all subtasks, given that they are all valid.
+/// By default, every subtask uses its first valid decomposition. Add [`Backtrack`] to search through the alternatives of the subtasks instead.
 #[derive(Debug, Component, Default, Reflect)]
 #[reflect(Component)]
 pub struct Sequence;
@@ -17,116 +21,133 @@ impl CompoundTask for Sequence {
     }
 }
 
+/// Makes the [`Sequence`] on the same entity backtrack during decomposition.
+/// When a subtask fails to decompose, the previous subtasks are asked for their next valid alternative,
+/// e.g. a [`Select`] will try its next valid branch. The [`Props`] are rolled back to the state before that subtask.
+/// This finds plans that the default greedy decomposition misses, at the cost of a potentially much more expensive search.
+#[derive(Debug, Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Backtrack;
+
+/// Upper bound for the number of subtask decompositions a single backtracking [`Sequence`] may attempt.
+const MAX_BACKTRACKING_STEPS: usize = 1024;
+
+/// A partial decomposition of a backtracking [`Sequence`].
+struct Frame {
+    plan: Plan,
+    world_state: Props,
+    alternative: usize,
+}
+
 fn decompose_sequence(
-    In(mut ctx): In<DecomposeInput>,
+    In(ctx): In<DecomposeInput>,
     world: &mut World,
     mut task_relations: Local<QueryState<&Tasks>>,
-    mut individual_tasks: Local<
-        QueryState<
-            (
-                Entity,
-                Has<Operator>,
-                Option<&TypeErasedCompoundTask>,
-                Option<&Conditions>,
-                Option<&Effects>,
-            ),
-            Or<(With<Operator>, With<TypeErasedCompoundTask>)>,
-        >,
-    >,
+    mut individual_tasks: Local<SubtaskQuery>,
     mut conditions: Local<QueryState<(Entity, &Condition)>>,
     mut effects: Local<QueryState<(Entity, &Effect)>>,
-    mut individual_tasks_scratch: Local<
-        Vec<(
-            Entity,
-            bool,
-            Option<TypeErasedCompoundTask>,
-            Option<Conditions>,
-            Option<Effects>,
-        )>,
-    >,
 ) -> DecomposeResult {
-    let Ok(tasks) = task_relations.get(world, ctx.compound_task) else {
+    let Some(subtasks) = Subtask::collect(
+        world,
+        &mut task_relations,
+        &mut individual_tasks,
+        ctx.compound_task,
+    ) else {
         return DecomposeResult::Failure;
     };
-    individual_tasks_scratch.extend(individual_tasks.iter_many(world, tasks).map(
-        |(task_entity, has_operator, compound_task, condition_relations, effect_relations)| {
-            (
-                task_entity,
-                has_operator,
-                compound_task.cloned(),
-                condition_relations.cloned(),
-                effect_relations.cloned(),
-            )
-        },
-    ));
-    let mut found_anything = false;
-    for (task_entity, has_operator, compound_task, condition_relations, effect_relations) in
-        individual_tasks_scratch.drain(..)
-    {
-        let mut individual_conditions = Vec::new();
-        if let Some(condition_relations) = condition_relations {
-            for (entity, condition) in conditions.iter_many(world, condition_relations.iter()) {
-                if !condition.is_fullfilled(&mut ctx.world_state) {
-                    return DecomposeResult::Failure;
-                }
-                individual_conditions.push(entity);
+    let backtrack = world.get::<Backtrack>(ctx.compound_task).is_some();
+    let mut decomposer = ExclusiveDecomposer {
+        world,
+        conditions: &mut conditions,
+        effects: &mut effects,
+    };
+    decompose_in_order(&mut decomposer, ctx, &subtasks, backtrack)
+}
+
+/// Decomposes all subtasks in order, which is what [`Sequence`] does. See [`Backtrack`] for what `backtrack` does.
+pub(crate) fn decompose_in_order(
+    decomposer: &mut impl Decomposer,
+    ctx: DecomposeInput,
+    subtasks: &[Subtask],
+    backtrack: bool,
+) -> DecomposeResult {
+    if subtasks.is_empty() {
+        return DecomposeResult::Failure;
+    }
+    if !backtrack && ctx.skip > 0 {
+        // A greedy sequence only has a single decomposition
+        return DecomposeResult::Failure;
+    }
+
+    // `frames[i]` holds the state before decomposing the `i`th subtask, as well as the alternative we are trying for it.
+    let mut frames = vec![Frame {
+        plan: ctx.plan,
+        world_state: ctx.world_state,
+        alternative: 0,
+    }];
+    let mut skip = ctx.skip;
+    let mut steps = 0;
+    loop {
+        let i = frames.len() - 1;
+        if i == subtasks.len() {
+            if skip == 0 {
+                let frame = frames.pop().unwrap();
+                return DecomposeResult::Success {
+                    plan: frame.plan,
+                    world_state: frame.world_state,
+                };
             }
-        }
-        let conditions = if !found_anything {
-            // Only the first "entry" subtask needs to inherit our conditions
-            ctx.conditions.extend(individual_conditions);
-            ctx.conditions.clone()
+            // The caller wants another alternative of this sequence
+            skip -= 1;
+            frames.pop();
         } else {
-            individual_conditions
-        };
-        if has_operator {
-            ctx.plan.push_back(PlannedOperator {
-                entity: task_entity,
-                effects: vec![],
-                conditions,
-            });
-        } else if let Some(compound_task) = compound_task {
-            let result = world.run_system_with(
-                compound_task.decompose,
+            steps += 1;
+            if steps > MAX_BACKTRACKING_STEPS {
+                debug!(
+                    compound_task=?ctx.compound_task,
+                    "exceeded the maximum number of backtracking steps, aborting decomposition"
+                );
+                return DecomposeResult::Failure;
+            }
+            let frame = &frames[i];
+            let subtask = &subtasks[i];
+            let result = decomposer.decompose_subtask(
+                subtask,
                 DecomposeInput {
                     planner: ctx.planner,
-                    compound_task: task_entity,
-                    world_state: ctx.world_state.clone(),
-                    plan: ctx.plan.clone(),
+                    compound_task: subtask.entity,
+                    world_state: frame.world_state.clone(),
+                    plan: frame.plan.clone(),
                     previous_mtr: ctx.previous_mtr.clone(),
-                    conditions,
+                    // Only the first "entry" subtask needs to inherit our conditions
+                    conditions: if i == 0 {
+                        ctx.conditions.clone()
+                    } else {
+                        Vec::new()
+                    },
+                    skip: frame.alternative,
                 },
             );
-            world.flush();
             match result {
-                Ok(DecomposeResult::Success { plan, world_state }) => {
-                    ctx.plan = plan;
-                    ctx.world_state = world_state;
+                DecomposeResult::Success { plan, world_state } => {
+                    frames.push(Frame {
+                        plan,
+                        world_state,
+                        alternative: 0,
+                    });
+                    continue;
+                }
+                DecomposeResult::Rejection => return DecomposeResult::Rejection,
+                DecomposeResult::Failure if !backtrack => return DecomposeResult::Failure,
+                DecomposeResult::Failure => {
+                    frames.pop();
                 }
-                Ok(DecomposeResult::Rejection) => return DecomposeResult::Rejection,
-                Ok(DecomposeResult::Failure) | Err(_) => return DecomposeResult::Failure,
             }
-        } else {
-            unreachable!()
         }
-        if ctx.plan.is_empty() {
+        // Retry the previous subtask with its next alternative
+        let Some(previous) = frames.last_mut() else {
             return DecomposeResult::Failure;
-        }
-        if let Some(effect_relations) = effect_relations {
-            for (entity, effect) in effects.iter_many(world, effect_relations.iter()) {
-                effect.apply(&mut ctx.world_state);
-                ctx.plan.back_mut().unwrap().effects.push(entity);
-            }
-        }
-        found_anything = true;
-    }
-
-    if found_anything {
-        DecomposeResult::Success {
-            plan: ctx.plan,
-            world_state: ctx.world_state,
-        }
-    } else {
-        DecomposeResult::Failure
+        };
+        previous.alternative += 1;
     }
 }
@@ -0,0 +1,122 @@
+//! Contains the [`UtilitySelect`] [`CompoundTask`] and the [`Utility`] scores of its subtasks.
+
+use alloc::sync::Arc;
+use core::fmt::Debug;
+
+use ustr::Ustr;
+
+use crate::{
+    prelude::*,
+    task::compound::{
+        DecomposeId, DecomposeInput, DecomposeResult, ExclusiveDecomposer, Subtask, SubtaskQuery,
+        decompose_first_valid,
+    },
+};
+
+/// A [`CompoundTask`] that decomposes into the valid subtask with the highest [`Utility`].
+/// Subtasks are tried in descending order of their score, and subtasks with equal scores are tried in the order they were declared in.
+/// Subtasks without a [`Utility`] have a score of `0.0`.
+///
+/// The [`Mtr`](crate::plan::mtr::Mtr) records the rank of the chosen subtask, not its score,
+/// so a replan interrupts it when a valid subtask ranks at or above that position with the current scores.
+#[derive(Debug, Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct UtilitySelect;
+
+impl CompoundTask for UtilitySelect {
+    fn register_decompose(commands: &mut Commands) -> DecomposeId {
+        commands.register_system(decompose_utility_select)
+    }
+}
+
+/// The score of a subtask of a [`UtilitySelect`]. Higher scores are tried first.
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Utility {
+    #[reflect(ignore, default = "Utility::zero")]
+    score: Arc<dyn Fn(&mut Props) -> f32 + Send + Sync + 'static>,
+}
+
+impl PartialEq for Utility {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.score, &other.score)
+    }
+}
+impl Eq for Utility {}
+
+impl Utility {
+    /// Creates a new utility with the given scoring function.
+    pub fn new(score: impl Fn(&mut Props) -> f32 + Send + Sync + 'static) -> Self {
+        Self {
+            score: Arc::new(score),
+        }
+    }
+
+    /// Evaluates the score with the given properties.
+    /// It will insert props holding default values if they are queried, but are not yet present in [`Props`].
+    pub fn score(&self, props: &mut Props) -> f32 {
+        (self.score)(props)
+    }
+
+    /// Shorthand for creating a utility that always has the same score.
+    pub fn fixed(score: f32) -> Self {
+        Self::new(move |_| score)
+    }
+
+    /// Shorthand for creating a utility that uses `props[name]` as its score.
+    pub fn prop(name: impl Into<Ustr>) -> Self {
+        let name = name.into();
+        Self::new(move |props| *props.get_mut::<f32>(name))
+    }
+
+    fn zero() -> Arc<dyn Fn(&mut Props) -> f32 + Send + Sync + 'static> {
+        Arc::new(|_| 0.0)
+    }
+}
+
+impl Debug for Utility {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Utility")
+            .field("score", &"<callback>")
+            .finish()
+    }
+}
+
+fn decompose_utility_select(
+    In(mut ctx): In<DecomposeInput>,
+    world: &mut World,
+    mut task_relations: Local<QueryState<&Tasks>>,
+    mut individual_tasks: Local<SubtaskQuery>,
+    mut utilities: Local<QueryState<&Utility>>,
+    mut conditions: Local<QueryState<(Entity, &Condition)>>,
+    mut effects: Local<QueryState<(Entity, &Effect)>>,
+) -> DecomposeResult {
+    let Some(subtasks) = Subtask::collect(
+        world,
+        &mut task_relations,
+        &mut individual_tasks,
+        ctx.compound_task,
+    ) else {
+        return DecomposeResult::Failure;
+    };
+
+    let mut ranked = Vec::with_capacity(subtasks.len());
+    for subtask in &subtasks {
+        let score = utilities
+            .get(world, subtask.entity)
+            .map_or(0.0, |utility| utility.score(&mut ctx.world_state));
+        ranked.push((score, subtask));
+    }
+    // `sort_by` is stable, so ties keep the declaration order
+    ranked.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    let mut decomposer = ExclusiveDecomposer {
+        world,
+        conditions: &mut conditions,
+        effects: &mut effects,
+    };
+    decompose_first_valid(
+        &mut decomposer,
+        ctx,
+        ranked.into_iter().map(|(_, subtask)| subtask),
+    )
+}
@@ -4,6 +4,7 @@ use crate::prelude::*;
 
 pub mod compound;
 pub mod operator;
+pub mod shared;
 pub(crate) mod validation;
 
 /// The return type of [`Operator`]s.
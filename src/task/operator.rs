@@ -11,7 +11,13 @@ use crate::task::validation::BaeTaskPresent;
 /// The exact type of [`SystemId`] valid for [`Operator`]s.
 pub type OperatorId = SystemId<In<OperatorInput>, OperatorStatus>;
 
+/// The exact type of [`SystemId`] valid for the lifecycle hooks of [`Operator`]s. See [`OperatorHook`].
+pub type OperatorHookId = SystemId<In<OperatorInput>>;
+
+type RegisterHook = Box<dyn FnOnce(&mut Commands) -> OperatorHookId + Send + Sync>;
+
 /// The smallest unit of a plan, representing a single step. Contains a system that gets called for you during the execution of the plan.
+/// Optionally, systems can be run when the operator starts, completes, or is aborted. See [`Operator::on_enter`], [`Operator::on_exit`] and [`Operator::on_abort`].
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 #[component(on_insert = Self::on_insert_hook, on_replace = Self::on_replace_hook)]
@@ -21,6 +27,46 @@ pub struct Operator {
     register_system: Option<Box<dyn FnOnce(&mut Commands) -> OperatorId + Send + Sync>>,
     #[reflect(ignore)]
     system_id: Option<OperatorId>,
+    /// Whether the system is owned by someone else, e.g. the [`BehaviorRegistry`](crate::registry::BehaviorRegistry), and must not be unregistered by us.
+    #[reflect(ignore)]
+    shared: bool,
+    #[reflect(ignore)]
+    register_hooks: Vec<(OperatorHook, RegisterHook)>,
+    #[reflect(ignore)]
+    hooks: OperatorHooks,
+}
+
+/// The points in the lifecycle of an [`Operator`] at which a hook system can be run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+pub enum OperatorHook {
+    /// The operator is about to run for the first time, i.e. it just became the current step of the [`Plan`] and its conditions are met.
+    Enter,
+    /// The operator returned [`OperatorStatus::Success`], its [`Effect::verify`] effects matched, and the plan is about to move on to the next step.
+    Exit,
+    /// The operator was entered, but will not complete, because it returned [`OperatorStatus::Failure`], its conditions were no longer met,
+    /// its system returned an error, one of its [`Effect::verify`] effects did not match, or the [`Plan`] was replaced.
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct OperatorHooks {
+    enter: Option<OperatorHookId>,
+    exit: Option<OperatorHookId>,
+    abort: Option<OperatorHookId>,
+}
+
+impl OperatorHooks {
+    fn get_mut(&mut self, hook: OperatorHook) -> &mut Option<OperatorHookId> {
+        match hook {
+            OperatorHook::Enter => &mut self.enter,
+            OperatorHook::Exit => &mut self.exit,
+            OperatorHook::Abort => &mut self.abort,
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = OperatorHookId> {
+        [self.enter, self.exit, self.abort].into_iter().flatten()
+    }
 }
 
 impl Clone for Operator {
@@ -28,6 +74,9 @@ impl Clone for Operator {
         Self {
             register_system: None,
             system_id: self.system_id,
+            shared: self.shared,
+            register_hooks: Vec::new(),
+            hooks: self.hooks,
         }
     }
 }
@@ -44,6 +93,7 @@ impl Debug for Operator {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_struct("Operator")
             .field("system_id", &self.system_id)
+            .field("hooks", &self.hooks)
             .finish()
     }
 }
@@ -59,6 +109,25 @@ impl Operator {
         Self {
             system_id: None,
             register_system: Some(Box::new(move |commands| commands.register_system(system))),
+            shared: false,
+            register_hooks: Vec::new(),
+            hooks: OperatorHooks::default(),
+        }
+    }
+
+    /// Creates a new operator that runs an already registered system.
+    /// The system is not unregistered when the operator is removed, so it can be shared by many operators,
+    /// which also means that they share the state of the system, such as its [`Local`]s.
+    ///
+    /// Usually, you want to register the system with [`RegisterBehaviorExt::register_operator`](crate::registry::RegisterBehaviorExt::register_operator)
+    /// and refer to it with a [`NamedOperator`](crate::registry::NamedOperator) instead.
+    pub fn from_system_id(system_id: OperatorId) -> Self {
+        Self {
+            system_id: Some(system_id),
+            register_system: None,
+            shared: true,
+            register_hooks: Vec::new(),
+            hooks: OperatorHooks::default(),
         }
     }
 
@@ -67,30 +136,103 @@ impl Operator {
         Self::new(|_: In<OperatorInput>| OperatorStatus::Success)
     }
 
+    /// Runs the given system right before the operator runs for the first time. See [`OperatorHook::Enter`].
+    /// Useful for setup logic, such as starting an animation.
+    pub fn on_enter<S, M>(self, system: S) -> Self
+    where
+        S: IntoSystem<In<OperatorInput>, (), M>,
+        S::System: Send + Sync + 'static,
+    {
+        self.with_hook(OperatorHook::Enter, system)
+    }
+
+    /// Runs the given system after the operator returned [`OperatorStatus::Success`]. See [`OperatorHook::Exit`].
+    pub fn on_exit<S, M>(self, system: S) -> Self
+    where
+        S: IntoSystem<In<OperatorInput>, (), M>,
+        S::System: Send + Sync + 'static,
+    {
+        self.with_hook(OperatorHook::Exit, system)
+    }
+
+    /// Runs the given system when the operator was entered, but is discarded before completing. See [`OperatorHook::Abort`].
+    /// Useful for teardown logic, such as stopping an animation.
+    pub fn on_abort<S, M>(self, system: S) -> Self
+    where
+        S: IntoSystem<In<OperatorInput>, (), M>,
+        S::System: Send + Sync + 'static,
+    {
+        self.with_hook(OperatorHook::Abort, system)
+    }
+
+    /// Runs the given system at the given point in the lifecycle of the operator.
+    /// Setting a hook that was already set replaces it.
+    pub fn with_hook<S, M>(mut self, hook: OperatorHook, system: S) -> Self
+    where
+        S: IntoSystem<In<OperatorInput>, (), M>,
+        S::System: Send + Sync + 'static,
+    {
+        let system = IntoSystem::into_system(system);
+        let register_hook: RegisterHook =
+            Box::new(move |commands| commands.register_system(system));
+        self.register_hooks
+            .retain(|(registered, _)| *registered != hook);
+        self.register_hooks.push((hook, register_hook));
+        self
+    }
+
     /// Returns the [`SystemId`] of the registered operator one-shot system.
     pub fn system_id(&self) -> OperatorId {
         self.system_id.unwrap()
     }
 
+    /// Returns the [`SystemId`] of the registered one-shot system for the given hook, if any.
+    pub fn hook(&self, hook: OperatorHook) -> Option<OperatorHookId> {
+        match hook {
+            OperatorHook::Enter => self.hooks.enter,
+            OperatorHook::Exit => self.hooks.exit,
+            OperatorHook::Abort => self.hooks.abort,
+        }
+    }
+
     fn on_insert_hook(mut world: DeferredWorld, context: HookContext) {
-        let Some(register_system) = world
-            .get_mut::<Self>(context.entity)
-            .and_then(|mut task_system| task_system.register_system.take())
+        let Some((register_system, register_hooks)) =
+            world.get_mut::<Self>(context.entity).map(|mut operator| {
+                (
+                    operator.register_system.take(),
+                    core::mem::take(&mut operator.register_hooks),
+                )
+            })
         else {
             return;
         };
-        let system_id = register_system(&mut world.commands());
-        world.get_mut::<Self>(context.entity).unwrap().system_id = Some(system_id);
+        if let Some(register_system) = register_system {
+            let system_id = register_system(&mut world.commands());
+            world.get_mut::<Self>(context.entity).unwrap().system_id = Some(system_id);
+        }
+        for (hook, register_hook) in register_hooks {
+            let system_id = register_hook(&mut world.commands());
+            *world
+                .get_mut::<Self>(context.entity)
+                .unwrap()
+                .hooks
+                .get_mut(hook) = Some(system_id);
+        }
     }
 
     fn on_replace_hook(mut world: DeferredWorld, context: HookContext) {
-        let Some(system_id) = world
-            .get::<Self>(context.entity)
-            .and_then(|tt| tt.system_id)
-        else {
+        let Some(operator) = world.get::<Self>(context.entity) else {
             return;
         };
-        world.commands().unregister_system(system_id);
+        let system_id = operator.system_id.filter(|_| !operator.shared);
+        let hooks = operator.hooks;
+        let mut commands = world.commands();
+        if let Some(system_id) = system_id {
+            commands.unregister_system(system_id);
+        }
+        for hook_id in hooks.iter() {
+            commands.unregister_system(hook_id);
+        }
     }
 }
 
@@ -0,0 +1,58 @@
+//! Contains the [`UsesDomain`] relationship for sharing a single task hierarchy between many planners.
+//!
+//! Usually, every entity holding a [`Plan`] also holds its own copy of the tasks spawned with [`tasks!`].
+//! For many agents with the same behavior, this quickly adds up to a lot of task, condition and effect entities.
+//! Instead, you can spawn the hierarchy once and point all agents to its root:
+//!
+//! ```
+//! # use bevy::prelude::*;
+//! # use bevy_bae::prelude::*;
+//! # fn walk(_: In<OperatorInput>) -> OperatorStatus { OperatorStatus::Success }
+//! # fn look_around(_: In<OperatorInput>) -> OperatorStatus { OperatorStatus::Success }
+//! fn spawn_villagers(mut commands: Commands) {
+//!     let behavior = commands
+//!         .spawn((
+//!             Name::new("villager behavior"),
+//!             Sequence,
+//!             tasks![Operator::new(walk), Operator::new(look_around)],
+//!         ))
+//!         .id();
+//!     for _ in 0..2000 {
+//!         commands
+//!             .spawn((Plan::new(), UsesDomain(behavior)))
+//!             .trigger(UpdatePlan::new);
+//!     }
+//! }
+//! ```
+//!
+//! The [`Props`], [`Plan`] and [`RandomSeed`] stay on each agent, and [`OperatorInput::entity`] still refers to the agent.
+//! Keep in mind that state stored on the task entities themselves, e.g. in an [`Operator`] system's [`Local`]s, is shared between all agents.
+
+use alloc::slice;
+use core::iter::Copied;
+
+use crate::prelude::*;
+
+/// Points from an entity holding a [`Plan`] to the root of a task hierarchy that is planned with instead of the entity's own tasks.
+/// The root can hold [`Conditions`] and [`Effects`] just like the entity holding the [`Plan`] would.
+///
+/// The root itself should not hold a [`Plan`]. Despawning it removes this component from all its users.
+#[derive(Component, Deref, Reflect, Debug, PartialEq, Eq, Clone)]
+#[relationship(relationship_target = DomainUsers)]
+#[reflect(Component)]
+pub struct UsesDomain(pub Entity);
+
+/// Relationship target for [`UsesDomain`]. Lists all entities planning with this task hierarchy.
+#[derive(Component, Clone, Deref, Reflect, Debug, Default, PartialEq, Eq)]
+#[relationship_target(relationship = UsesDomain)]
+#[reflect(Component)]
+pub struct DomainUsers(Vec<Entity>);
+
+impl<'a> IntoIterator for &'a DomainUsers {
+    type Item = Entity;
+    type IntoIter = Copied<slice::Iter<'a, Entity>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -0,0 +1,145 @@
+//! Tests reusing decompositions with a [`PlanCache`]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::prelude::*;
+use common::*;
+
+#[test]
+fn reuses_plan_for_same_props() {
+    let mut app = App::test_cached((
+        ReplanPolicy::every_ticks(1),
+        Select,
+        tasks![
+            (op("a"), conditions![Condition::eq("enabled", true)]),
+            op_ongoing("idle"),
+        ],
+    ));
+    assert_eq!(app.cache().misses(), 1);
+    assert_eq!(app.cache().len(), 1);
+    for _ in 0..3 {
+        app.update();
+        app.assert_ran(["idle"]);
+    }
+    assert!(app.cache().hits() > 0);
+    assert_eq!(app.cache().misses(), 1);
+
+    app.behavior_entity().set_prop("enabled", true);
+    app.update();
+    app.assert_ran(["a"]);
+    assert_eq!(app.cache().misses(), 2);
+    assert_eq!(app.cache().len(), 2);
+}
+
+#[test]
+fn reuses_failed_decomposition() {
+    let mut app = App::test_cached((
+        ReplanPolicy::every_ticks(1),
+        Sequence,
+        tasks![(op("a"), conditions![Condition::eq("enabled", true)])],
+    ));
+    app.update();
+    app.assert_ran([]);
+    assert!(app.cache().hits() > 0);
+    assert_eq!(app.cache().misses(), 1);
+
+    app.behavior_entity().set_prop("enabled", true);
+    app.update();
+    app.assert_ran(["a"]);
+}
+
+#[test]
+fn keeps_running_plan_for_failed_props() {
+    let mut app = App::test_cached((
+        ReplanPolicy::every_ticks(1),
+        Select,
+        tasks![
+            (
+                Sequence,
+                tasks![op("prepare"), op_ongoing("work")],
+                conditions![Condition::eq("enabled", true)],
+            ),
+            (op("fallback"), conditions![Condition::eq("fallback", true)]),
+        ],
+    ));
+    app.update();
+    app.assert_ran([]);
+
+    app.behavior_entity().set_prop("enabled", true);
+    app.update();
+    app.update();
+    app.assert_ran(["work"]);
+
+    // Planning with these props failed before, but the fallback is now rejected in favor of the running plan
+    app.behavior_entity().set_prop("enabled", false);
+    app.update();
+    app.assert_ran(["work"]);
+}
+
+#[test]
+fn does_not_cache_custom_conditions() {
+    let mut app = App::test_cached((
+        ReplanPolicy::every_ticks(1),
+        Select,
+        tasks![
+            (op("a"), conditions![Condition::new(|_| false)]),
+            op_ongoing("idle"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["idle"]);
+    assert!(app.cache().is_empty());
+    assert_eq!(app.cache().hits(), 0);
+    assert_eq!(app.cache().misses(), 0);
+}
+
+#[test]
+fn does_not_cache_custom_effects() {
+    let mut app = App::test_cached((
+        ReplanPolicy::every_ticks(1),
+        Sequence,
+        tasks![
+            (op("a"), effects![Effect::inc::<i32>("count", 1)]),
+            (op_ongoing("b"), conditions![Condition::eq("count", 1)]),
+        ],
+    ));
+    app.update();
+    assert!(app.cache().is_empty());
+    assert_eq!(app.cache().hits(), 0);
+    assert_eq!(app.cache().misses(), 0);
+}
+
+#[test]
+fn clears_when_hierarchy_changes() {
+    let mut app = App::test_cached((
+        ReplanPolicy::every_ticks(1),
+        Select,
+        tasks![
+            (op("a"), conditions![Condition::eq("enabled", true)]),
+            op_ongoing("idle"),
+        ],
+    ));
+    assert_eq!(app.cache().len(), 1);
+
+    let root = app.behavior_entity().id();
+    app.world_mut().spawn((op("b"), TaskOf(root)));
+    assert!(app.cache().is_empty());
+}
+
+trait CacheApp {
+    fn test_cached(behavior: impl Bundle) -> App;
+    fn cache(&self) -> &PlanCache;
+}
+
+impl CacheApp for App {
+    fn test_cached(behavior: impl Bundle) -> App {
+        App::test_with(behavior, |app| {
+            app.init_resource::<PlanCache>();
+        })
+    }
+
+    fn cache(&self) -> &PlanCache {
+        self.world().resource::<PlanCache>()
+    }
+}
@@ -0,0 +1,132 @@
+//! The app setup shared by the integration tests. Not every test uses every helper.
+#![allow(dead_code)]
+
+// The following functions are not reflective of real user code and are here to make the test suite more simple to set up.
+
+use bevy::{log::LogPlugin, prelude::*, time::TimeUpdateStrategy};
+use bevy_bae::prelude::*;
+use std::sync::Mutex;
+
+pub trait TestApp {
+    /// Creates an app with the [`BaePlugin`] that runs the fixed schedule once per update, without spawning anything.
+    /// [`Ran`] is cleared at the start of every update.
+    fn test_empty() -> App;
+    /// Like [`TestApp::test_with`], without any extra setup.
+    fn test(behavior: impl Bundle) -> App;
+    /// Spawns `behavior` as an entity named "root" and plans it in the first update, without running the plan yet.
+    /// `setup` is called before the first update.
+    fn test_with(behavior: impl Bundle, setup: impl FnOnce(&mut App)) -> App;
+    #[track_caller]
+    fn assert_ran<const N: usize>(&self, names: [&'static str; N]);
+    /// The single entity holding a [`Plan`].
+    fn behavior_entity(&mut self) -> EntityWorldMut<'_>;
+    /// The first entity with the given [`Name`].
+    fn find_entity(&mut self, name: &str) -> Entity;
+}
+
+impl TestApp for App {
+    fn test_empty() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            LogPlugin {
+                filter: format!(
+                    "bevy_log=off,bevy_bae=debug,{default}",
+                    default = bevy::log::DEFAULT_FILTER
+                ),
+                ..default()
+            },
+            BaePlugin::default(),
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(
+            Time::<Fixed>::default().timestep(),
+        ))
+        .init_resource::<Ran>()
+        .add_systems(PreUpdate, |mut ran: ResMut<Ran>| {
+            ran.0.clear();
+        });
+        app
+    }
+
+    fn test(behavior: impl Bundle) -> App {
+        App::test_with(behavior, |_| {})
+    }
+
+    fn test_with(behavior: impl Bundle, setup: impl FnOnce(&mut App)) -> App {
+        let mut app = App::test_empty();
+        let behavior = Mutex::new(Some(behavior));
+        app.add_systems(Startup, move |mut commands: Commands| {
+            commands
+                .spawn(behavior.lock().unwrap().take().unwrap())
+                .insert_if_new(Name::new("root"))
+                .trigger(UpdatePlan::new);
+        });
+        setup(&mut app);
+        app.finish();
+        app.update();
+        app.assert_ran([]);
+        app
+    }
+
+    #[track_caller]
+    fn assert_ran<const N: usize>(&self, expected: [&'static str; N]) {
+        let expected = expected.map(ToString::to_string).to_vec();
+        let actual = self.world().resource::<Ran>().0.clone();
+        assert_eq!(expected, actual);
+    }
+
+    fn behavior_entity(&mut self) -> EntityWorldMut<'_> {
+        let entity = self
+            .world()
+            .try_query_filtered::<Entity, With<Plan>>()
+            .unwrap()
+            .single(self.world())
+            .unwrap();
+        self.world_mut().entity_mut(entity)
+    }
+
+    fn find_entity(&mut self, name: &str) -> Entity {
+        self.world_mut()
+            .query::<(Entity, &Name)>()
+            .iter(self.world())
+            .find(|(_, entity_name)| entity_name.as_str() == name)
+            .unwrap()
+            .0
+    }
+}
+
+/// The names of the operators that ran in the current update, in order.
+#[derive(Resource, Default)]
+pub struct Ran(pub Vec<String>);
+
+/// An operator that records its name in [`Ran`] and succeeds.
+pub fn op(name: &str) -> impl Bundle {
+    op_with_status(name, OperatorStatus::Success)
+}
+
+/// An operator that records its name in [`Ran`] and never finishes.
+pub fn op_ongoing(name: &str) -> impl Bundle {
+    op_with_status(name, OperatorStatus::Ongoing)
+}
+
+/// An operator that records its name in [`Ran`] and returns `status`.
+pub fn op_with_status(name: &str, status: OperatorStatus) -> impl Bundle {
+    let name = name.to_string();
+    (
+        Name::new(name.clone()),
+        Operator::new(
+            move |_: In<OperatorInput>, mut ran: ResMut<Ran>| -> OperatorStatus {
+                ran.0.push(name.clone());
+                status
+            },
+        ),
+    )
+}
+
+/// An operator system that records `name` in [`Ran`] and succeeds. Useful for registering operators by name.
+pub fn ran(name: &'static str) -> impl Fn(In<OperatorInput>, ResMut<Ran>) -> OperatorStatus {
+    move |_: In<OperatorInput>, mut ran: ResMut<Ran>| {
+        ran.0.push(name.to_string());
+        OperatorStatus::Success
+    }
+}
@@ -0,0 +1,186 @@
+//! Tests combining conditions
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::prelude::*;
+use common::*;
+
+#[test]
+fn all_requires_every_condition() {
+    let condition = Condition::all([Condition::eq("a", true), Condition::eq("b", true)]);
+    assert!(!condition.is_fullfilled(&mut props([("a", true)])));
+    assert!(condition.is_fullfilled(&mut props([("a", true), ("b", true)])));
+    assert!(Condition::all([]).is_fullfilled(&mut Props::new()));
+}
+
+#[test]
+fn any_requires_one_condition() {
+    let condition = Condition::any([Condition::eq("a", true), Condition::eq("b", true)]);
+    assert!(!condition.is_fullfilled(&mut Props::new()));
+    assert!(condition.is_fullfilled(&mut props([("b", true)])));
+    assert!(!Condition::any([]).is_fullfilled(&mut Props::new()));
+}
+
+#[test]
+fn not_inverts_condition() {
+    let condition = Condition::not(Condition::eq("a", true));
+    assert!(condition.is_fullfilled(&mut Props::new()));
+    assert!(!condition.is_fullfilled(&mut props([("a", true)])));
+}
+
+#[test]
+fn nests_conditions() {
+    let condition = Condition::any([
+        Condition::in_range("health", ..30.0),
+        Condition::all([
+            Condition::not(Condition::eq("in_cover", true)),
+            Condition::always_true(),
+        ]),
+    ]);
+    let mut props = props([("in_cover", true)]);
+    props.set("health", 50.0);
+    assert!(!condition.is_fullfilled(&mut props));
+    props.set("health", 10.0);
+    assert!(condition.is_fullfilled(&mut props));
+    props.set("health", 50.0);
+    props.set("in_cover", false);
+    assert!(condition.is_fullfilled(&mut props));
+}
+
+#[test]
+fn exposes_structure() {
+    let lt = Condition::lt("health", 30.0);
+    assert_eq!(
+        lt.expr(),
+        &ConditionExpr::Compare {
+            name: "health".into(),
+            op: CompareOp::Lt,
+            value: 30.0.into(),
+        }
+    );
+    let condition = Condition::any([lt.clone(), Condition::not(Condition::always_false())]);
+    let ConditionExpr::Any(children) = condition.expr() else {
+        panic!("expected any, got {:?}", condition.expr());
+    };
+    assert_eq!(children[0], lt);
+    assert!(
+        matches!(children[1].expr(), ConditionExpr::Not(inner) if inner.expr() == &ConditionExpr::Const(false))
+    );
+    assert_eq!(Condition::new(|_| true).expr(), &ConditionExpr::Custom);
+
+    assert_eq!(
+        condition.to_string(),
+        format!("(health < {:?} || !false)", Value::from(30.0))
+    );
+    assert_eq!(
+        Condition::all([
+            Condition::in_range("health", 0.0..30.0),
+            Condition::in_range("ammo", 1.0..),
+        ])
+        .to_string(),
+        "(0 <= health < 30 && 1 <= ammo)"
+    );
+}
+
+#[test]
+fn plans_with_any_condition() {
+    let mut app = App::test((
+        Select,
+        tasks![
+            (
+                op("flee"),
+                conditions![Condition::any([
+                    Condition::eq("scared", true),
+                    Condition::eq("hurt", true),
+                ])],
+            ),
+            op("attack"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["attack"]);
+
+    app.behavior_entity().set_prop("hurt", true);
+    app.update();
+    app.assert_ran(["flee"]);
+}
+
+#[test]
+fn plans_with_system_condition() {
+    let mut app = App::test((
+        Select,
+        tasks![
+            (op("flee"), conditions![SystemCondition::new(is_scared)]),
+            op("attack"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["attack"]);
+
+    app.insert_resource(Scared(true));
+    app.update();
+    app.assert_ran(["flee"]);
+}
+
+#[test]
+fn checks_system_condition_before_running() {
+    let mut app = App::test((
+        Sequence,
+        tasks![
+            op("walk"),
+            (op("flee"), conditions![SystemCondition::new(is_scared)]),
+        ],
+    ));
+    app.insert_resource(Scared(true));
+    app.update();
+    app.assert_ran(["walk"]);
+
+    app.insert_resource(Scared(false));
+    app.update();
+    app.assert_ran([]);
+    assert!(matches!(
+        app.behavior_entity().get::<PlanFailure>().unwrap().reason,
+        PlanFailureReason::UnmetCondition { .. }
+    ));
+}
+
+#[test]
+fn skips_runtime_only_system_condition_while_planning() {
+    let mut app = App::test((
+        Select,
+        tasks![
+            (
+                op("flee"),
+                conditions![SystemCondition::new(is_scared).runtime_only()],
+            ),
+            op("attack"),
+        ],
+    ));
+    // Planning assumed that we are scared, but we are not
+    app.update();
+    app.assert_ran([]);
+    let failure = app.behavior_entity().get::<PlanFailure>().unwrap().clone();
+    let PlanFailureReason::UnmetCondition { condition, .. } = failure.reason else {
+        panic!("expected an unmet condition, got {failure:?}");
+    };
+    assert!(app.world().entity(condition).contains::<SystemCondition>());
+}
+
+// The following functions are not reflective of real user code and are here to make the test suite more simple to set up.
+
+#[derive(Resource, Default)]
+struct Scared(bool);
+
+fn is_scared(input: In<ConditionInput>, scared: Option<Res<Scared>>, names: Query<&Name>) -> bool {
+    assert_eq!(names.get(input.entity).unwrap().as_str(), "root");
+    scared.is_some_and(|scared| scared.0)
+}
+
+fn props<const N: usize>(values: [(&'static str, bool); N]) -> Props {
+    let mut props = Props::new();
+    for (name, value) in values {
+        props.set(name, value);
+    }
+    props
+}
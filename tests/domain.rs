@@ -0,0 +1,328 @@
+//! Tests data-driven domains
+
+mod common;
+
+use bevy::{asset::AssetPlugin, prelude::*};
+use bevy_bae::{
+    domain::{DomainCondition, DomainEffect, DomainError, DomainTask, DomainTaskKind},
+    prelude::*,
+};
+use common::*;
+
+const RON: &str = r#"
+(
+    name: "root",
+    task: Select([
+        (
+            name: "attack",
+            task: Operator("attack"),
+            conditions: [Eq("enemy_visible", true)],
+        ),
+        (
+            task: Sequence([
+                (name: "walk", task: Operator("walk"), effects: [Set("walked", true)]),
+                (name: "look", task: Operator("look"), effects: [PlanOnly(Set("bored", true))]),
+            ]),
+        ),
+    ]),
+)
+"#;
+
+const JSON: &str = r#"
+{
+    "name": "root",
+    "task": { "Select": [
+        {
+            "name": "attack",
+            "task": { "Operator": "attack" },
+            "conditions": [{ "Eq": ["enemy_visible", true] }]
+        },
+        {
+            "task": { "Sequence": [
+                { "name": "walk", "task": { "Operator": "walk" }, "effects": [{ "Set": ["walked", true] }] },
+                { "name": "look", "task": { "Operator": "look" }, "effects": [{ "PlanOnly": { "Set": ["bored", true] } }] }
+            ] }
+        }
+    ] }
+}
+"#;
+
+#[test]
+fn parses_ron_and_json() {
+    let ron = Domain::from_ron(RON.as_bytes()).unwrap();
+    let json = Domain::from_json(JSON.as_bytes()).unwrap();
+    assert_eq!(ron, json);
+    assert_eq!(ron.root.name.as_deref(), Some("root"));
+    let DomainTaskKind::Select(subtasks) = &ron.root.task else {
+        panic!("expected a select, got {:?}", ron.root.task);
+    };
+    assert_eq!(
+        subtasks[0],
+        DomainTask {
+            name: Some("attack".to_string()),
+            task: DomainTaskKind::Operator("attack".to_string()),
+            conditions: vec![DomainCondition::Eq(
+                "enemy_visible".to_string(),
+                true.into()
+            )],
+            effects: vec![],
+        }
+    );
+    let DomainTaskKind::Sequence(subtasks) = &subtasks[1].task else {
+        panic!("expected a sequence, got {:?}", subtasks[1].task);
+    };
+    assert_eq!(
+        subtasks[1].effects,
+        vec![DomainEffect::PlanOnly(Box::new(DomainEffect::Set(
+            "bored".to_string(),
+            true.into()
+        )))]
+    );
+}
+
+#[test]
+fn spawns_domain_from_asset() {
+    let mut app = App::test_domains();
+    app.spawn_domain(RON);
+    app.update();
+    app.assert_ran(["walk"]);
+    assert!(*app.behavior_entity().get_prop::<bool>("walked"));
+
+    app.update();
+    app.assert_ran(["look"]);
+    // Plan-only effects are not applied
+    assert!(!*app.behavior_entity().get_prop::<bool>("bored"));
+
+    app.behavior_entity().set_prop("enemy_visible", true);
+    app.update();
+    app.assert_ran(["attack"]);
+}
+
+#[test]
+fn spawned_domain_matches_hierarchy() {
+    let mut app = App::test_domains();
+    let entity = app.spawn_domain(RON);
+    let world = app.world_mut();
+    let root = world.entity(entity);
+    assert!(root.contains::<Select>());
+    assert!(root.contains::<Plan>());
+    assert_eq!(root.get::<Name>().unwrap().as_str(), "root");
+
+    let subtasks = root.get::<Tasks>().unwrap().to_vec();
+    assert_eq!(subtasks.len(), 2);
+    let attack = world.entity(subtasks[0]);
+    assert!(attack.contains::<Operator>());
+    assert_eq!(attack.get::<Conditions>().unwrap().len(), 1);
+    let sequence = world.entity(subtasks[1]);
+    assert!(sequence.contains::<Sequence>());
+    assert_eq!(sequence.get::<Tasks>().unwrap().len(), 2);
+}
+
+#[test]
+fn respawns_modified_domain() {
+    let mut app = App::test_domains();
+    let entity = app.spawn_domain(RON);
+    app.update();
+    app.assert_ran(["walk"]);
+
+    app.modify_domain(entity, r#"(name: "root", task: Operator("attack"))"#);
+    // The asset event is only sent at the end of this update, so the old plan keeps running
+    app.update();
+    app.assert_ran(["look"]);
+
+    app.update();
+    app.assert_ran(["attack"]);
+    // Props are kept
+    assert!(*app.behavior_entity().get_prop::<bool>("walked"));
+
+    let world = app.world_mut();
+    let root = world.entity(entity);
+    assert!(root.contains::<Operator>());
+    assert!(!root.contains::<Select>());
+    assert!(!root.contains::<Tasks>());
+    assert!(!root.contains::<Conditions>());
+    let remaining_subtasks = world.query::<&TaskOf>().iter(world).count();
+    assert_eq!(remaining_subtasks, 0);
+}
+
+#[test]
+fn keeps_old_domain_on_invalid_modification() {
+    let mut app = App::test_domains();
+    let entity = app.spawn_domain(RON);
+    app.modify_domain(entity, r#"(task: Operator("fly"))"#);
+    app.update();
+    app.update();
+
+    let root = app.world().entity(entity);
+    assert!(root.contains::<Select>());
+    assert_eq!(root.get::<Tasks>().unwrap().len(), 2);
+}
+
+#[test]
+fn fails_on_unknown_operator() {
+    let mut app = App::test_domains();
+    let domain = Domain {
+        root: DomainTask {
+            name: None,
+            task: DomainTaskKind::Sequence(vec![
+                DomainTask {
+                    name: None,
+                    task: DomainTaskKind::Operator("walk".to_string()),
+                    conditions: vec![],
+                    effects: vec![],
+                },
+                DomainTask {
+                    name: None,
+                    task: DomainTaskKind::Operator("fly".to_string()),
+                    conditions: vec![],
+                    effects: vec![],
+                },
+            ]),
+            conditions: vec![],
+            effects: vec![],
+        },
+    };
+    let entity = app.world_mut().spawn_empty().id();
+    assert_eq!(
+        domain.spawn(app.world_mut(), entity),
+        Err(DomainError::UnknownOperator("fly".to_string()))
+    );
+    assert!(!app.world().entity(entity).contains::<Sequence>());
+}
+
+#[test]
+fn retries_domain_with_unknown_operator() {
+    let mut app = App::test_domains();
+    let entity = app.spawn_domain(r#"(task: Operator("fly"))"#);
+    app.update();
+    assert!(!app.world().entity(entity).contains::<Operator>());
+
+    app.register_operator("fly", ran("fly"));
+    app.update();
+    let root = app.world().entity(entity);
+    assert!(root.contains::<Operator>());
+    assert!(root.contains::<Plan>());
+}
+
+#[test]
+fn retries_invalid_modification() {
+    let mut app = App::test_domains();
+    let entity = app.spawn_domain(RON);
+    app.modify_domain(entity, r#"(task: Operator("fly"))"#);
+    app.update();
+    app.update();
+    assert!(app.world().entity(entity).contains::<Select>());
+
+    app.register_operator("fly", ran("fly"));
+    app.update();
+    let root = app.world().entity(entity);
+    assert!(!root.contains::<Select>());
+    assert!(root.contains::<Operator>());
+}
+
+#[test]
+fn spawns_named_conditions_and_effects() {
+    let mut app = App::test_domains();
+    app.spawn_domain(
+        r#"(
+            task: Select([
+                (name: "attack", task: Operator("attack"), conditions: [Named("enemy_visible")]),
+                (name: "look", task: Operator("look"), effects: [Named("spot_enemy")]),
+            ]),
+        )"#,
+    );
+    app.update();
+    app.assert_ran(["look"]);
+    assert!(*app.behavior_entity().get_prop::<bool>("enemy_visible"));
+
+    app.update();
+    app.assert_ran(["attack"]);
+}
+
+#[test]
+fn spawns_expressions() {
+    let mut app = App::test_domains();
+    app.spawn_domain(
+        r#"(
+            task: Select([
+                (name: "attack", task: Operator("attack"), conditions: [Expr("enemy_visible && !tired")]),
+                (name: "look", task: Operator("look"), effects: [Expr("enemy_visible = true")]),
+            ]),
+        )"#,
+    );
+    app.update();
+    app.assert_ran(["look"]);
+
+    app.update();
+    app.assert_ran(["attack"]);
+}
+
+#[test]
+fn fails_on_invalid_expression() {
+    let mut app = App::test_domains();
+    let domain =
+        Domain::from_ron(br#"(task: Operator("walk"), effects: [Expr("walked = ")])"#).unwrap();
+    let entity = app.world_mut().spawn_empty().id();
+    let Err(DomainError::InvalidExpression { expression, error }) =
+        domain.spawn(app.world_mut(), entity)
+    else {
+        panic!("expected an invalid expression");
+    };
+    assert_eq!(expression, "walked = ");
+    assert_eq!(error.span, 9..9);
+}
+
+#[test]
+fn fails_on_unknown_condition() {
+    let mut app = App::test_domains();
+    let domain =
+        Domain::from_ron(br#"(task: Operator("walk"), conditions: [Named("is_flying")])"#).unwrap();
+    let entity = app.world_mut().spawn_empty().id();
+    assert_eq!(
+        domain.spawn(app.world_mut(), entity),
+        Err(DomainError::UnknownCondition("is_flying".to_string()))
+    );
+}
+
+trait DomainApp {
+    fn test_domains() -> App;
+    fn spawn_domain(&mut self, ron: &str) -> Entity;
+    fn modify_domain(&mut self, entity: Entity, ron: &str);
+}
+
+impl DomainApp for App {
+    fn test_domains() -> App {
+        let mut app = App::test_empty();
+        app.add_plugins((AssetPlugin::default(), DomainPlugin))
+            .register_operator("attack", ran("attack"))
+            .register_operator("walk", ran("walk"))
+            .register_operator("look", ran("look"))
+            .register_condition("enemy_visible", Condition::eq("enemy_visible", true))
+            .register_effect("spot_enemy", Effect::set("enemy_visible", true));
+        app.finish();
+        app
+    }
+
+    fn spawn_domain(&mut self, ron: &str) -> Entity {
+        let domain = Domain::from_ron(ron.as_bytes()).unwrap();
+        let handle = self
+            .world_mut()
+            .resource_mut::<Assets<Domain>>()
+            .add(domain);
+        let entity = self.world_mut().spawn(DomainHandle(handle)).id();
+        // Spawns the domain and plans, but does not run the plan yet
+        self.update();
+        self.assert_ran([]);
+        entity
+    }
+
+    fn modify_domain(&mut self, entity: Entity, ron: &str) {
+        let domain = Domain::from_ron(ron.as_bytes()).unwrap();
+        let handle = self.world().get::<DomainHandle>(entity).unwrap().0.clone();
+        *self
+            .world_mut()
+            .resource_mut::<Assets<Domain>>()
+            .get_mut(&handle)
+            .unwrap() = domain;
+    }
+}
@@ -0,0 +1,252 @@
+//! Tests the events triggered during the lifecycle of a plan
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::prelude::*;
+use common::*;
+
+#[test]
+fn triggers_replace_plan() {
+    let mut app = App::test_events((Sequence, tasks![op("a")]));
+    // The plan was computed during the setup
+    app.assert_events(["replaced"]);
+
+    app.update();
+    app.assert_events(["started a", "finished a Success", "completed"]);
+
+    // The empty plan is recomputed in the next fixed frame
+    app.update();
+    app.assert_events(["replaced", "started a", "finished a Success", "completed"]);
+}
+
+#[test]
+fn triggers_operator_events_in_order() {
+    let mut app = App::test_events((Sequence, tasks![op("a"), op_ongoing("b")]));
+    app.clear_events();
+
+    app.update();
+    app.assert_events(["started a", "finished a Success"]);
+
+    app.update();
+    app.assert_events(["started b"]);
+
+    app.update();
+    app.assert_events([]);
+}
+
+#[test]
+fn triggers_plan_failed_on_operator_failure() {
+    let mut app = App::test_events((Sequence, tasks![op_fail("a"), op("b")]));
+    app.clear_events();
+
+    app.update();
+    app.assert_events(["started a", "finished a Failure", "failed OperatorFailed a"]);
+}
+
+#[test]
+fn triggers_plan_failed_on_unmet_condition() {
+    let mut app = App::test_events((
+        Sequence,
+        tasks![(op_ongoing("a"), cond_is("disabled", false))],
+    ));
+    app.update();
+    app.clear_events();
+
+    app.behavior_entity().set_prop("disabled", true);
+    app.update();
+    app.assert_events(["finished a Failure", "failed UnmetCondition"]);
+}
+
+#[test]
+fn triggers_plan_failed_on_missing_operator() {
+    let mut app = App::test_events((Sequence, tasks![op("a"), op("b")]));
+    app.clear_events();
+
+    let b = app.find_entity("b");
+    app.world_mut().entity_mut(b).remove::<Operator>();
+    app.update();
+    app.assert_events(["started a", "finished a Success"]);
+
+    app.update();
+    app.assert_events(["failed MissingOperator b"]);
+}
+
+#[test]
+fn triggers_plan_failed_on_system_error() {
+    let mut app = App::test_events((
+        Sequence,
+        tasks![(
+            Name::new("a"),
+            Operator::new(|_: In<OperatorInput>, _: Single<&Transform>| {
+                OperatorStatus::Success
+            })
+        )],
+    ));
+    app.clear_events();
+
+    app.update();
+    app.assert_events(["started a", "finished a Failure", "failed SystemError a"]);
+}
+
+#[test]
+fn triggers_effect_mismatch() {
+    let mut app = App::test_events((
+        Sequence,
+        tasks![
+            (
+                op("a"),
+                effects![(Name::new("arrived"), Effect::set("arrived", true).verify())],
+            ),
+            op("b"),
+        ],
+    ));
+    app.clear_events();
+
+    app.update();
+    app.assert_events([
+        "started a",
+        "mismatch a arrived",
+        "finished a Failure",
+        "failed EffectMismatch arrived",
+    ]);
+}
+
+#[test]
+fn stores_last_failure() {
+    let mut app = App::test_events((
+        Select,
+        tasks![
+            (op_fail("a"), cond_is("failed_once", false)),
+            (op_ongoing("b"), cond_is("disabled", false)),
+        ],
+    ));
+    assert!(app.behavior_entity().get::<PlanFailure>().is_none());
+
+    app.update();
+    let a = app.find_entity("a");
+    let first = app.behavior_entity().get::<PlanFailure>().unwrap().clone();
+    assert_eq!(
+        first.reason,
+        PlanFailureReason::OperatorFailed { operator: a }
+    );
+    assert_eq!(first.entity(), a);
+
+    app.behavior_entity().set_prop("failed_once", true);
+    app.update();
+    app.update();
+    assert_eq!(
+        app.behavior_entity().get::<PlanFailure>(),
+        Some(&first),
+        "succeeding steps keep the last failure"
+    );
+
+    app.behavior_entity().set_prop("disabled", true);
+    app.update();
+    let second = app.behavior_entity().get::<PlanFailure>().unwrap().clone();
+    let PlanFailureReason::UnmetCondition { operator, .. } = second.reason else {
+        panic!("expected an unmet condition, got {second:?}");
+    };
+    assert_eq!(operator, app.find_entity("b"));
+    assert!(
+        second
+            .tick
+            .is_newer_than(first.tick, app.world().read_change_tick())
+    );
+}
+
+trait EventsApp {
+    fn test_events(behavior: impl Bundle) -> App;
+    #[track_caller]
+    fn assert_events<const N: usize>(&mut self, expected: [&'static str; N]);
+    fn clear_events(&mut self);
+}
+
+impl EventsApp for App {
+    fn test_events(behavior: impl Bundle) -> App {
+        App::test_with(behavior, |app| {
+            app.init_resource::<EventLog>()
+                .add_observer(|_: On<ReplacePlan>, mut log: ResMut<EventLog>| {
+                    log.0.push("replaced".to_string());
+                })
+                .add_observer(|_: On<PlanCompleted>, mut log: ResMut<EventLog>| {
+                    log.0.push("completed".to_string());
+                })
+                .add_observer(
+                    |failed: On<PlanFailed>, names: Query<&Name>, mut log: ResMut<EventLog>| {
+                        let (kind, entity) = match &failed.failure.reason {
+                            PlanFailureReason::UnmetCondition { condition, .. } => {
+                                ("UnmetCondition", *condition)
+                            }
+                            PlanFailureReason::OperatorFailed { operator } => {
+                                ("OperatorFailed", *operator)
+                            }
+                            PlanFailureReason::SystemError { operator, .. } => {
+                                ("SystemError", *operator)
+                            }
+                            PlanFailureReason::MissingOperator { operator } => {
+                                ("MissingOperator", *operator)
+                            }
+                            PlanFailureReason::EffectMismatch { effect } => {
+                                ("EffectMismatch", *effect)
+                            }
+                        };
+                        // Conditions are not named in these tests
+                        let name = names
+                            .get(entity)
+                            .map_or(String::new(), |name| format!(" {name}"));
+                        log.0.push(format!("failed {kind}{name}"));
+                    },
+                )
+                .add_observer(
+                    |mismatch: On<EffectMismatch>,
+                     names: Query<&Name>,
+                     mut log: ResMut<EventLog>| {
+                        let operator = names.get(mismatch.operator).unwrap();
+                        let effect = names.get(mismatch.effect).unwrap();
+                        log.0.push(format!("mismatch {operator} {effect}"));
+                    },
+                )
+                .add_observer(
+                    |started: On<OperatorStarted>,
+                     names: Query<&Name>,
+                     mut log: ResMut<EventLog>| {
+                        let name = names.get(started.operator).unwrap();
+                        log.0.push(format!("started {name}"));
+                    },
+                )
+                .add_observer(
+                    |finished: On<OperatorFinished>,
+                     names: Query<&Name>,
+                     mut log: ResMut<EventLog>| {
+                        let name = names.get(finished.operator).unwrap();
+                        log.0.push(format!("finished {name} {:?}", finished.status));
+                    },
+                );
+        })
+    }
+
+    #[track_caller]
+    fn assert_events<const N: usize>(&mut self, expected: [&'static str; N]) {
+        let expected = expected.map(ToString::to_string).to_vec();
+        let actual = core::mem::take(&mut self.world_mut().resource_mut::<EventLog>().0);
+        assert_eq!(expected, actual);
+    }
+
+    fn clear_events(&mut self) {
+        self.world_mut().resource_mut::<EventLog>().0.clear();
+    }
+}
+
+// The following functions are not reflective of real user code and are here to make the test suite more simple to set up.
+
+#[derive(Resource, Default)]
+struct EventLog(Vec<String>);
+
+fn op_fail(name: &str) -> impl Bundle {
+    op_with_status(name, OperatorStatus::Failure)
+}
+
+fn cond_is(name: &str, val: impl Into<Value>) -> impl Bundle {
+    conditions![Condition::eq(name, val)]
+}
@@ -42,6 +42,71 @@ fn skips_plan_only_effect() {
     app.assert_last_opt("a");
 }
 
+#[test]
+fn continues_plan_on_verified_effect() {
+    let mut app = App::test((
+        Sequence,
+        tasks![
+            (op("a"), effects![Effect::set("use_b", true).verify()]),
+            (op("b"), cond_is("use_b", true)),
+        ],
+    ));
+    // the outside world fulfills the effect
+    app.behavior_entity().props_mut().set("use_b", true);
+    app.update();
+    app.assert_last_opt("a");
+
+    app.update();
+    app.assert_last_opt("b");
+}
+
+#[test]
+fn replans_on_effect_mismatch() {
+    let mut app = App::test((
+        Sequence,
+        tasks![
+            (op("a"), effects![Effect::set("use_b", true).verify()]),
+            (op("b"), cond_is("use_b", true)),
+        ],
+    ));
+    // plan, but we didn't actually set use_b, so abort plan right away
+    app.update();
+    app.assert_last_opt("a");
+    assert!(matches!(
+        app.behavior_entity().get::<PlanFailure>().unwrap().reason,
+        PlanFailureReason::EffectMismatch { .. }
+    ));
+    assert!(!*app.behavior_entity().get_prop::<bool>("use_b"));
+
+    // replan same plan
+    app.update();
+    app.assert_last_opt("a");
+}
+
+#[test]
+fn verifies_effect_with_condition() {
+    let mut app =
+        App::test((
+            Sequence,
+            tasks![
+                (
+                    op("a"),
+                    effects![
+                        Effect::mutate("ammo", 1.0, |a, b| *a += b)
+                            .verify_with(Condition::compare("ammo", CompareOp::Ge, 1.0))
+                    ],
+                ),
+                (op("b"), cond_is("ammo", 1.0)),
+            ],
+        ));
+    app.behavior_entity().props_mut().set("ammo", 1.0);
+    app.update();
+    app.assert_last_opt("a");
+
+    app.update();
+    app.assert_last_opt("b");
+}
+
 #[test]
 fn runs_plan_then_replans_with_new_effects() {
     let mut app = App::test((
@@ -362,6 +427,104 @@ fn compound_effects_are_not_applied_on_abort() {
     assert!(!*app.behavior_entity().get_prop::<bool>("called"));
 }
 
+#[test]
+fn runs_enter_and_exit_hooks_on_success() {
+    let mut app = App::test((Sequence, tasks![op_hooked("a", OperatorStatus::Success)]));
+    app.update();
+    app.assert_last_opt("a");
+    app.assert_hooks(["enter a", "exit a"]);
+}
+
+#[test]
+fn runs_abort_hook_on_failure() {
+    let mut app = App::test((Sequence, tasks![op_hooked("a", OperatorStatus::Failure)]));
+    app.update();
+    app.assert_last_opt("a");
+    app.assert_hooks(["enter a", "abort a"]);
+}
+
+#[test]
+fn runs_enter_hook_only_once() {
+    let mut app = App::test((Sequence, tasks![op_hooked("a", OperatorStatus::Ongoing)]));
+    app.update();
+    app.assert_hooks(["enter a"]);
+
+    app.update();
+    app.update();
+    app.assert_last_opt("a");
+    app.assert_hooks(["enter a"]);
+}
+
+#[test]
+fn runs_abort_hook_on_unmet_conditions() {
+    let mut app = App::test((
+        Sequence,
+        tasks![(
+            op_hooked("a", OperatorStatus::Ongoing),
+            cond_is("disabled", false)
+        )],
+    ));
+    app.update();
+    app.assert_hooks(["enter a"]);
+
+    app.behavior_entity().set_prop("disabled", true);
+    app.update();
+    app.assert_last_opt(None);
+    app.assert_hooks(["enter a", "abort a"]);
+}
+
+#[test]
+fn runs_abort_hook_on_replan() {
+    let mut app = App::test((
+        Select,
+        tasks![
+            (op("b"), cond_is("enabled", true)),
+            op_hooked("a", OperatorStatus::Ongoing),
+        ],
+    ));
+    app.update();
+    app.assert_last_opt("a");
+    app.assert_hooks(["enter a"]);
+
+    app.behavior_entity().set_prop("enabled", true);
+    app.behavior_entity().trigger(UpdatePlan::new);
+    app.update();
+    app.assert_last_opt("b");
+    app.assert_hooks(["enter a", "abort a"]);
+}
+
+#[test]
+fn runs_abort_hook_on_replan_during_enter() {
+    let mut app = App::test((
+        Select,
+        tasks![
+            (op("b"), cond_is("enabled", true)),
+            (
+                Name::new("a"),
+                Operator::new(|_: In<OperatorInput>| OperatorStatus::Ongoing)
+                    .on_enter(
+                        |input: In<OperatorInput>,
+                         mut props: Query<&mut Props>,
+                         mut log: ResMut<HookLog>,
+                         mut commands: Commands| {
+                            log.0.push("enter a".to_string());
+                            props.get_mut(input.entity).unwrap().set("enabled", true);
+                            commands.entity(input.entity).trigger(UpdatePlan::new);
+                        },
+                    )
+                    .on_abort(|_: In<OperatorInput>, mut log: ResMut<HookLog>| {
+                        log.0.push("abort a".to_string());
+                    }),
+            ),
+        ],
+    ));
+    app.update();
+    app.assert_hooks(["enter a", "abort a"]);
+
+    app.update();
+    app.assert_last_opt("b");
+}
+
 #[test]
 fn logs_plan() {
     let mut app = App::test((
@@ -387,6 +550,8 @@ trait TestApp {
     fn test(behavior: impl Bundle) -> App;
     #[track_caller]
     fn assert_last_opt(&self, name: impl Into<Option<&'static str>>);
+    #[track_caller]
+    fn assert_hooks<const N: usize>(&self, expected: [&'static str; N]);
     fn behavior_entity(&mut self) -> EntityWorldMut<'_>;
 }
 
@@ -409,6 +574,7 @@ impl TestApp for App {
             Time::<Fixed>::default().timestep(),
         ))
         .init_resource::<LastOpt>()
+        .init_resource::<HookLog>()
         .add_systems(Startup, move |mut commands: Commands| {
             commands
                 .spawn(behavior.lock().unwrap().take().unwrap())
@@ -432,6 +598,13 @@ impl TestApp for App {
         assert_eq!(expected, actual);
     }
 
+    #[track_caller]
+    fn assert_hooks<const N: usize>(&self, expected: [&'static str; N]) {
+        let expected = expected.map(ToString::to_string).to_vec();
+        let actual = self.world().resource::<HookLog>().0.clone();
+        assert_eq!(expected, actual);
+    }
+
     fn behavior_entity(&mut self) -> EntityWorldMut<'_> {
         let entity = self
             .world()
@@ -447,6 +620,9 @@ impl TestApp for App {
 #[derive(Resource, Default)]
 struct LastOpt(Option<String>);
 
+#[derive(Resource, Default)]
+struct HookLog(Vec<String>);
+
 fn op(name: &str) -> impl Bundle {
     let name = name.to_string();
     (
@@ -460,6 +636,29 @@ fn op(name: &str) -> impl Bundle {
     )
 }
 
+/// An operator that always returns `status` and logs its hooks to [`HookLog`].
+fn op_hooked(name: &str, status: OperatorStatus) -> impl Bundle {
+    let name = name.to_string();
+    let hook = |kind: &'static str, name: String| {
+        move |_: In<OperatorInput>, mut log: ResMut<HookLog>| {
+            log.0.push(format!("{kind} {name}"));
+        }
+    };
+    (
+        Name::new(name.clone()),
+        Operator::new({
+            let name = name.clone();
+            move |_: In<OperatorInput>, mut last_opt: ResMut<LastOpt>| -> OperatorStatus {
+                last_opt.0 = Some(name.clone());
+                status
+            }
+        })
+        .on_enter(hook("enter", name.clone()))
+        .on_exit(hook("exit", name.clone()))
+        .on_abort(hook("abort", name)),
+    )
+}
+
 fn cond_is(name: &str, val: impl Into<Value>) -> impl Bundle {
     conditions![Condition::eq(name, val)]
 }
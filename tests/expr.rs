@@ -0,0 +1,173 @@
+//! Tests parsing conditions and effects from expressions
+
+use bevy::prelude::*;
+use bevy_bae::{
+    expr::{ParseError, ParseErrorKind},
+    prelude::*,
+};
+
+#[test]
+fn parses_comparisons() {
+    let condition = Condition::parse("health < 30").unwrap();
+    assert_eq!(
+        condition.expr(),
+        &ConditionExpr::Compare {
+            name: "health".into(),
+            op: CompareOp::Lt,
+            value: 30.0.into(),
+        }
+    );
+    for (expression, op) in [
+        ("a == 1", CompareOp::Eq),
+        ("a != 1", CompareOp::Ne),
+        ("a <= 1", CompareOp::Le),
+        ("a > 1", CompareOp::Gt),
+        ("a>=1", CompareOp::Ge),
+    ] {
+        let condition = Condition::parse(expression).unwrap();
+        assert!(
+            matches!(condition.expr(), ConditionExpr::Compare { op: actual, .. } if *actual == op),
+            "{expression}: {condition:?}"
+        );
+    }
+}
+
+#[test]
+fn parses_values() {
+    for (expression, value) in [
+        ("a == -0.5", Value::from(-0.5)),
+        ("a == true", true.into()),
+        ("a == \"player\"", "player".into()),
+    ] {
+        let condition = Condition::parse(expression).unwrap();
+        let ConditionExpr::Compare { value: actual, .. } = condition.expr() else {
+            panic!("{expression}: {condition:?}");
+        };
+        assert_eq!(*actual, value, "{expression}");
+    }
+}
+
+#[test]
+fn respects_precedence() {
+    let condition = Condition::parse("a || b && !c").unwrap();
+    let ConditionExpr::Any(any) = condition.expr() else {
+        panic!("expected any, got {condition:?}");
+    };
+    assert_eq!(any.len(), 2);
+    let ConditionExpr::All(all) = any[1].expr() else {
+        panic!("expected all, got {:?}", any[1]);
+    };
+    assert_eq!(all.len(), 2);
+    assert!(matches!(all[1].expr(), ConditionExpr::Not(_)));
+
+    let condition = Condition::parse("(a || b) && c").unwrap();
+    assert!(matches!(condition.expr(), ConditionExpr::All(all) if all.len() == 2));
+}
+
+#[test]
+fn evaluates_conditions() {
+    let condition = Condition::parse("health < 30 && !in_cover").unwrap();
+    let mut props = Props::new();
+    props.set("health", 50.0);
+    assert!(!condition.is_fullfilled(&mut props));
+    props.set("health", 10.0);
+    assert!(condition.is_fullfilled(&mut props));
+    props.set("in_cover", true);
+    assert!(!condition.is_fullfilled(&mut props));
+}
+
+#[test]
+fn applies_effects() {
+    let mut props = Props::new();
+    props.set("ammo", 3.0);
+    props.set("reloading", true);
+    Effect::parse("ammo -= 1; reloading = false")
+        .unwrap()
+        .apply(&mut props);
+    assert_eq!(*props.get_mut::<f32>("ammo"), 2.0);
+    assert!(!*props.get_mut::<bool>("reloading"));
+
+    for (expression, expected) in [
+        ("ammo = 10", 10.0),
+        ("ammo += 2", 12.0),
+        ("ammo *= 2", 24.0),
+        ("ammo /= 4;", 6.0),
+    ] {
+        Effect::parse(expression).unwrap().apply(&mut props);
+        assert_eq!(*props.get_mut::<f32>("ammo"), expected, "{expression}");
+    }
+}
+
+#[test]
+fn parses_expected_outcome_of_effects() {
+    let mut props = Props::new();
+    let expected = Effect::parse("ammo = 10; reloading = false")
+        .unwrap()
+        .expected()
+        .unwrap()
+        .clone();
+    assert!(!expected.is_fullfilled(&mut props));
+    props.set("ammo", 10.0);
+    assert!(expected.is_fullfilled(&mut props));
+
+    assert!(
+        Effect::parse("ammo = 10; reloading -= 1")
+            .unwrap()
+            .expected()
+            .is_none()
+    );
+}
+
+#[test]
+fn reports_errors_with_spans() {
+    assert_eq!(
+        Condition::parse("health < ").unwrap_err(),
+        ParseError {
+            kind: ParseErrorKind::UnexpectedEnd {
+                expected: "a number, a boolean or a string"
+            },
+            span: 9..9,
+        }
+    );
+    assert_eq!(
+        Condition::parse("health < 30 && && ammo").unwrap_err().span,
+        15..17
+    );
+    assert_eq!(
+        Condition::parse("(a || b").unwrap_err(),
+        ParseError {
+            kind: ParseErrorKind::UnexpectedEnd { expected: "')'" },
+            span: 7..7,
+        }
+    );
+    assert_eq!(
+        Condition::parse("a == 1 b").unwrap_err(),
+        ParseError {
+            kind: ParseErrorKind::UnexpectedToken {
+                expected: "end of expression",
+                found: "b".to_string(),
+            },
+            span: 7..8,
+        }
+    );
+    assert_eq!(
+        Condition::parse("a == \"oops").unwrap_err(),
+        ParseError {
+            kind: ParseErrorKind::UnterminatedString,
+            span: 5..10,
+        }
+    );
+    assert_eq!(
+        Condition::parse("a # b").unwrap_err(),
+        ParseError {
+            kind: ParseErrorKind::UnexpectedCharacter('#'),
+            span: 2..3,
+        }
+    );
+    let error = Effect::parse("ammo < 1").unwrap_err();
+    assert_eq!(error.span, 5..6);
+    assert_eq!(
+        error.to_string(),
+        "expected '=', '+=', '-=', '*=' or '/=', found \"<\" at 5..6"
+    );
+}
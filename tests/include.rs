@@ -0,0 +1,127 @@
+//! Tests including subtrees with [`Include`]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::prelude::*;
+use common::*;
+
+#[test]
+fn decomposes_included_subtree() {
+    let mut app = App::test_included();
+    let flee = app
+        .world_mut()
+        .spawn((Sequence, tasks![op("run"), op("hide")]))
+        .id();
+    app.spawn_behavior((Sequence, tasks![Include(flee), op("attack")]));
+    app.update();
+    app.assert_ran(["run"]);
+
+    app.update();
+    app.assert_ran(["hide"]);
+
+    app.update();
+    app.assert_ran(["attack"]);
+}
+
+#[test]
+fn includes_the_same_subtree_twice() {
+    let mut app = App::test_included();
+    let step = app.world_mut().spawn(op("step")).id();
+    app.spawn_behavior((Sequence, tasks![Include(step), Include(step)]));
+    app.update();
+    app.assert_ran(["step"]);
+
+    app.update();
+    app.assert_ran(["step"]);
+    assert_eq!(app.world().entity(step).get::<TaskOf>(), None);
+}
+
+#[test]
+fn respects_conditions_of_include_and_target() {
+    let mut app = App::test_included();
+    let flee = app
+        .world_mut()
+        .spawn((op("flee"), cond_is("can_flee", true)))
+        .id();
+    app.spawn_behavior((
+        Select,
+        tasks![(Include(flee), cond_is("scared", true)), op("attack"),],
+    ));
+    app.update();
+    app.assert_ran(["attack"]);
+
+    app.behavior_entity().set_prop("scared", true);
+    app.update();
+    app.assert_ran(["attack"]);
+
+    app.behavior_entity().set_prop("can_flee", true);
+    app.update();
+    app.assert_ran(["flee"]);
+}
+
+#[test]
+fn fails_on_including_ancestor() {
+    let mut app = App::test_included();
+    let root = app.world_mut().spawn_empty().id();
+    app.world_mut().entity_mut(root).insert((
+        Select,
+        tasks![
+            (Sequence, tasks![op("loop"), Include(root)]),
+            op("fallback")
+        ],
+    ));
+    app.spawn_behavior_on(root);
+    app.update();
+    app.assert_ran(["fallback"]);
+}
+
+#[test]
+fn fails_on_mutually_recursive_includes() {
+    let mut app = App::test_included();
+    let a = app.world_mut().spawn_empty().id();
+    let b = app.world_mut().spawn_empty().id();
+    app.world_mut()
+        .entity_mut(a)
+        .insert((Sequence, tasks![op("a"), Include(b)]));
+    app.world_mut()
+        .entity_mut(b)
+        .insert((Sequence, tasks![op("b"), Include(a)]));
+    app.spawn_behavior((Select, tasks![Include(a), op("fallback")]));
+    app.update();
+    app.assert_ran(["fallback"]);
+}
+
+trait IncludeApp {
+    fn test_included() -> App;
+    fn spawn_behavior(&mut self, behavior: impl Bundle);
+    fn spawn_behavior_on(&mut self, entity: Entity);
+}
+
+impl IncludeApp for App {
+    fn test_included() -> App {
+        let mut app = App::test_empty();
+        app.finish();
+        app
+    }
+
+    fn spawn_behavior(&mut self, behavior: impl Bundle) {
+        let entity = self.world_mut().spawn(behavior).id();
+        self.spawn_behavior_on(entity);
+    }
+
+    fn spawn_behavior_on(&mut self, entity: Entity) {
+        self.world_mut()
+            .entity_mut(entity)
+            .insert((Name::new("root"), Plan::new()));
+        self.world_mut().trigger(UpdatePlan::new(entity));
+        self.update();
+        self.assert_ran([]);
+    }
+}
+
+// The following functions are not reflective of real user code and are here to make the test suite more simple to set up.
+
+fn cond_is(name: &str, val: impl Into<Value>) -> impl Bundle {
+    conditions![Condition::eq(name, val)]
+}
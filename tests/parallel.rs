@@ -0,0 +1,183 @@
+//! Tests the execution of parallel tasks
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::prelude::*;
+use common::*;
+
+#[test]
+fn all_succeed_runs_branches_concurrently() {
+    let mut app = App::test((
+        Sequence,
+        tasks![
+            (Parallel::default(), tasks![op_for("walk", 2), op("talk")]),
+            op("idle"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["walk", "talk"]);
+
+    app.update();
+    app.assert_ran(["walk"]);
+
+    app.update();
+    app.assert_ran(["idle"]);
+}
+
+#[test]
+fn all_succeed_aborts_on_failure() {
+    let mut app = App::test((
+        Sequence,
+        tasks![
+            (Parallel::default(), tasks![op_fail("a"), op_for("b", 3)]),
+            op("c"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["a", "b"]);
+
+    // the plan was aborted, so we start over instead of continuing with b
+    app.update();
+    app.assert_ran(["a", "b"]);
+}
+
+#[test]
+fn any_succeeds_ignores_failed_branches() {
+    let mut app = App::test((
+        Sequence,
+        tasks![
+            (
+                Parallel::new(ParallelPolicy::AnySucceeds),
+                tasks![op_fail("a"), op_for("b", 2)]
+            ),
+            op("c"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["a", "b"]);
+
+    app.update();
+    app.assert_ran(["b"]);
+
+    app.update();
+    app.assert_ran(["c"]);
+}
+
+#[test]
+fn any_succeeds_aborts_when_all_fail() {
+    let mut app = App::test((
+        Sequence,
+        tasks![
+            (
+                Parallel::new(ParallelPolicy::AnySucceeds),
+                tasks![op_fail("a"), op_fail("b")]
+            ),
+            op("c"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["a", "b"]);
+
+    // the plan was aborted, so we start over instead of continuing with c
+    app.update();
+    app.assert_ran(["a", "b"]);
+}
+
+#[test]
+fn first_finished_stops_other_branches() {
+    let mut app = App::test((
+        Sequence,
+        tasks![
+            (
+                Parallel::new(ParallelPolicy::FirstFinished),
+                tasks![op_for("a", 3), op("b")]
+            ),
+            op("c"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["a", "b"]);
+
+    app.update();
+    app.assert_ran(["c"]);
+}
+
+#[test]
+fn branches_can_be_sequences() {
+    let mut app = App::test((
+        Sequence,
+        tasks![
+            (
+                Parallel::default(),
+                tasks![(Sequence, tasks![op("a"), op("b")]), op_for("c", 2)]
+            ),
+            op("d"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["a", "c"]);
+
+    app.update();
+    app.assert_ran(["b", "c"]);
+
+    app.update();
+    app.assert_ran(["d"]);
+}
+
+#[test]
+fn branch_effects_are_planned_and_applied() {
+    let mut app = App::test((
+        Sequence,
+        tasks![
+            (
+                Parallel::default(),
+                tasks![(op("a"), eff("done", true)), op("b")]
+            ),
+            (op("c"), cond_is("done", true)),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["a", "b"]);
+    assert!(*app.behavior_entity().get_prop::<bool>("done"));
+
+    app.update();
+    app.assert_ran(["c"]);
+}
+
+// The following functions are not reflective of real user code and are here to make the test suite more simple to set up.
+
+/// An operator that is ongoing for `ticks - 1` ticks, and then succeeds.
+fn op_for(name: &str, ticks: u32) -> impl Bundle {
+    let name = name.to_string();
+    (
+        Name::new(name.clone()),
+        Operator::new(
+            move |_: In<OperatorInput>,
+                  mut ran: ResMut<Ran>,
+                  mut count: Local<u32>|
+                  -> OperatorStatus {
+                ran.0.push(name.clone());
+                *count += 1;
+                if *count < ticks {
+                    OperatorStatus::Ongoing
+                } else {
+                    *count = 0;
+                    OperatorStatus::Success
+                }
+            },
+        ),
+    )
+}
+
+fn op_fail(name: &str) -> impl Bundle {
+    op_with_status(name, OperatorStatus::Failure)
+}
+
+fn cond_is(name: &str, val: impl Into<Value>) -> impl Bundle {
+    conditions![Condition::eq(name, val)]
+}
+
+fn eff(name: &str, val: impl Into<Value>) -> impl Bundle {
+    effects![Effect::set(name, val)]
+}
@@ -211,8 +211,387 @@ fn effect_not_disabled_by_invalid_branch() {
     );
 }
 
+#[test]
+fn sequence_greedy_misses_later_branch() {
+    assert_plan(
+        (
+            Sequence,
+            tasks![
+                (
+                    Select,
+                    tasks![
+                        (op("a"), eff("ready", false)),
+                        (op("b"), eff("ready", true))
+                    ]
+                ),
+                (cond_is("ready", true), op("c")),
+            ],
+        ),
+        vec![],
+    );
+}
+
+#[test]
+fn sequence_backtracks_into_select() {
+    assert_plan(
+        (
+            Sequence,
+            Backtrack,
+            tasks![
+                (
+                    Select,
+                    tasks![
+                        (op("a"), eff("ready", false)),
+                        (op("b"), eff("ready", true))
+                    ]
+                ),
+                (cond_is("ready", true), op("c")),
+            ],
+        ),
+        vec!["b", "c"],
+    );
+}
+
+#[test]
+fn sequence_backtracks_with_rolled_back_props() {
+    assert_plan(
+        (
+            Sequence,
+            Backtrack,
+            tasks![
+                (Select, tasks![(op("a"), eff("tired", true)), op("b")]),
+                (cond_is("tired", false), op("c")),
+            ],
+        ),
+        vec!["b", "c"],
+    );
+}
+
+#[test]
+fn sequence_backtracks_across_multiple_siblings() {
+    assert_plan(
+        (
+            Sequence,
+            Backtrack,
+            tasks![
+                (
+                    Select,
+                    tasks![(op("a"), eff("x", false)), (op("b"), eff("x", true))]
+                ),
+                (
+                    Select,
+                    tasks![(op("c"), eff("y", false)), (op("d"), eff("y", true))]
+                ),
+                (cond_is("x", true), cond_is("y", true), op("e")),
+            ],
+        ),
+        vec!["b", "d", "e"],
+    );
+}
+
+#[test]
+fn sequence_backtracks_into_nested_backtracking_sequence() {
+    assert_plan(
+        (
+            Sequence,
+            Backtrack,
+            tasks![
+                (
+                    Sequence,
+                    Backtrack,
+                    tasks![
+                        (
+                            Select,
+                            tasks![(op("a"), eff("x", false)), (op("b"), eff("x", true))]
+                        ),
+                        op("c"),
+                    ]
+                ),
+                (cond_is("x", true), op("d")),
+            ],
+        ),
+        vec!["b", "c", "d"],
+    );
+}
+
+#[test]
+fn utility_select_highest_score() {
+    assert_plan(
+        (
+            UtilitySelect,
+            tasks![
+                (Utility::fixed(1.0), op("a")),
+                (Utility::fixed(3.0), op("b")),
+                (Utility::fixed(2.0), op("c")),
+            ],
+        ),
+        vec!["b"],
+    );
+}
+
+#[test]
+fn utility_select_skips_invalid() {
+    assert_plan(
+        (
+            UtilitySelect,
+            tasks![
+                (Utility::fixed(1.0), op("a")),
+                (Utility::fixed(3.0), cond(false), op("b")),
+                (Utility::fixed(2.0), op("c")),
+            ],
+        ),
+        vec!["c"],
+    );
+}
+
+#[test]
+fn utility_select_ties_use_declaration_order() {
+    assert_plan(
+        (
+            UtilitySelect,
+            tasks![
+                (Utility::fixed(-1.0), op("a")),
+                op("b"),
+                (Utility::fixed(0.0), op("c")),
+            ],
+        ),
+        vec!["b"],
+    );
+}
+
+#[test]
+fn utility_select_scores_props() {
+    assert_plan(
+        (
+            Sequence,
+            tasks![
+                (op("a"), eff("hunger", 5.0_f32)),
+                (
+                    UtilitySelect,
+                    tasks![
+                        (Utility::fixed(2.0), op("b")),
+                        (Utility::prop("hunger"), op("c")),
+                    ]
+                ),
+            ],
+        ),
+        vec!["a", "c"],
+    );
+}
+
+#[test]
+fn utility_select_interrupts_by_rank() {
+    let mut app = plan_app(
+        (
+            UtilitySelect,
+            tasks![
+                (
+                    Utility::new(|props| 3.0 - *props.get_mut::<f32>("tired")),
+                    op("a")
+                ),
+                (Utility::fixed(2.0), op("b")),
+            ],
+        ),
+        0,
+    );
+    assert_eq!(plan_names(&mut app), vec!["a"]);
+
+    // a now ranks behind b, so b takes the rank recorded for a and interrupts it,
+    // even though b scores lower than a did when it was planned
+    let root = app
+        .world_mut()
+        .query::<(Entity, &Plan)>()
+        .single(app.world())
+        .unwrap()
+        .0;
+    app.world_mut().entity_mut(root).set_prop("tired", 2.0_f32);
+    app.world_mut().trigger(UpdatePlan::new(root));
+    app.world_mut().flush();
+    assert_eq!(plan_names(&mut app), vec!["b"]);
+}
+
+#[test]
+fn random_select_never_picks_zero_weight() {
+    for seed in 0..8 {
+        assert_plan(
+            (
+                RandomSeed::new(seed),
+                RandomSelect,
+                tasks![(RandomWeight(0.0), op("a")), op("b")],
+            ),
+            vec!["b"],
+        );
+    }
+}
+
+#[test]
+fn random_select_skips_invalid() {
+    for seed in 0..8 {
+        assert_plan(
+            (
+                RandomSeed::new(seed),
+                RandomSelect,
+                tasks![(cond(false), op("a")), op("b"), (cond(false), op("c"))],
+            ),
+            vec!["b"],
+        );
+    }
+}
+
+#[test]
+fn random_select_empty() {
+    assert_plan((RandomSelect, tasks![]), vec![]);
+}
+
+#[test]
+fn random_select_is_reproducible() {
+    let behavior = || {
+        (
+            RandomSeed::new(42),
+            RandomSelect,
+            tasks![op("a"), op("b"), op("c"), op("d")],
+        )
+    };
+    assert_eq!(plan_names_of(behavior()), plan_names_of(behavior()));
+}
+
+#[test]
+fn random_select_does_not_depend_on_entities() {
+    // A seed of 0 would be derived from the entity
+    for seed in 1..9 {
+        let behavior = || {
+            (
+                RandomSeed::new(seed),
+                Sequence,
+                tasks![
+                    (RandomSelect, tasks![op("a"), op("b"), op("c"), op("d")]),
+                    (RandomSelect, tasks![op("e"), op("f"), op("g"), op("h")]),
+                ],
+            )
+        };
+        assert_eq!(
+            plan_names_of(behavior()),
+            plan_names_after_spawning(behavior(), 100)
+        );
+    }
+}
+
+#[test]
+fn random_select_differs_between_default_seeds() {
+    let behavior = || (RandomSelect, tasks![op("a"), op("b"), op("c"), op("d")]);
+    let first = plan_names_of(behavior());
+    assert!((1..16).any(|padding| plan_names_after_spawning(behavior(), padding) != first));
+}
+
+#[test]
+fn random_select_respects_weights() {
+    let mut picked_a = 0;
+    let mut picked_b = 0;
+    for seed in 0..64 {
+        let plan = plan_names_of((
+            RandomSeed::new(seed),
+            RandomSelect,
+            tasks![(RandomWeight(1.0), op("a")), (RandomWeight(7.0), op("b"))],
+        ));
+        match plan[0].as_str() {
+            "a" => picked_a += 1,
+            "b" => picked_b += 1,
+            _ => unreachable!(),
+        }
+    }
+    assert!(picked_a > 0);
+    assert!(picked_b > picked_a);
+}
+
+#[test]
+fn repeat_times() {
+    assert_plan(
+        (Repeat::times(3), tasks![op("a"), op("b")]),
+        vec!["a", "b", "a", "b", "a", "b"],
+    );
+}
+
+#[test]
+fn repeat_times_fail() {
+    assert_plan(
+        (
+            Repeat::times(3),
+            tasks![(cond_is("done", false), op("a"), eff("done", true))],
+        ),
+        vec![],
+    );
+}
+
+#[test]
+fn repeat_times_above_max_iterations_fails() {
+    assert_plan(
+        (Repeat::times(5).with_max_iterations(4), tasks![op("a")]),
+        vec![],
+    );
+}
+
+#[test]
+fn repeat_while_valid() {
+    assert_plan(
+        (
+            Sequence,
+            tasks![
+                (op("a"), eff("count", 0.0_f32)),
+                (
+                    Repeat::while_valid(),
+                    tasks![(
+                        conditions![Condition::lt("count", 3.0_f32)],
+                        op("b"),
+                        effects![Effect::mutate("count", 1.0_f32, |count, one| *count += one)],
+                    )]
+                ),
+            ],
+        ),
+        vec!["a", "b", "b", "b"],
+    );
+}
+
+#[test]
+fn repeat_while_valid_fail() {
+    assert_plan(
+        (Repeat::while_valid(), tasks![(cond(false), op("a"))]),
+        vec![],
+    );
+}
+
+#[test]
+fn repeat_while_valid_is_capped() {
+    assert_plan(
+        (
+            Repeat::while_valid().with_max_iterations(4),
+            tasks![op("a")],
+        ),
+        vec!["a", "a", "a", "a"],
+    );
+}
+
 #[track_caller]
 fn assert_plan(behavior: impl Bundle, plan: Vec<&'static str>) {
+    let plan_names = plan
+        .into_iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    assert_eq!(plan_names, plan_names_of(behavior));
+}
+
+fn plan_names_of(behavior: impl Bundle) -> Vec<String> {
+    plan_names_after_spawning(behavior, 0)
+}
+
+/// Like [`plan_names_of`], but spawns `padding` unrelated entities first so that the tasks get different entities.
+fn plan_names_after_spawning(behavior: impl Bundle, padding: usize) -> Vec<String> {
+    let mut app = plan_app(behavior, padding);
+    plan_names(&mut app)
+}
+
+/// Creates an app in which `behavior` was planned, but its plan did not run yet.
+fn plan_app(behavior: impl Bundle, padding: usize) -> App {
     let mut app = App::new();
     let behavior = Mutex::new(Some(behavior));
     app.add_plugins((
@@ -230,6 +609,9 @@ fn assert_plan(behavior: impl Bundle, plan: Vec<&'static str>) {
         Time::<Fixed>::default().timestep(),
     ))
     .add_systems(Startup, move |mut commands: Commands| {
+        for _ in 0..padding {
+            commands.spawn_empty();
+        }
         commands
             .spawn(behavior.lock().unwrap().take().unwrap())
             .insert_if_new(Name::new("root"))
@@ -237,6 +619,10 @@ fn assert_plan(behavior: impl Bundle, plan: Vec<&'static str>) {
     });
     app.finish();
     app.update();
+    app
+}
+
+fn plan_names(app: &mut App) -> Vec<String> {
     let actual_plan = app
         .world()
         .try_query::<&Plan>()
@@ -249,7 +635,7 @@ fn assert_plan(behavior: impl Bundle, plan: Vec<&'static str>) {
         .world()
         .try_query_filtered::<(Entity, &Name), With<Operator>>()
         .unwrap();
-    let actual_plan_names = actual_plan
+    actual_plan
         .operators_left
         .into_iter()
         .map(|planned_op| {
@@ -258,14 +644,7 @@ fn assert_plan(behavior: impl Bundle, plan: Vec<&'static str>) {
                 .find_map(|(op, name)| (op == planned_op.entity).then(|| name.to_string()))
                 .unwrap()
         })
-        .collect::<Vec<_>>();
-
-    let plan_names = plan
-        .into_iter()
-        .map(ToString::to_string)
-        .collect::<Vec<_>>();
-
-    assert_eq!(plan_names, actual_plan_names);
+        .collect::<Vec<_>>()
 }
 
 // The following functions are not reflective of real user code and are here to make the test suite more simple to set up.
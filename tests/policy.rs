@@ -0,0 +1,152 @@
+//! Tests replanning according to a [`ReplanPolicy`]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::prelude::*;
+use common::*;
+
+#[test]
+fn replans_every_few_ticks() {
+    let mut app = App::test((
+        ReplanPolicy::every_ticks(3),
+        Select,
+        tasks![
+            (op("a"), conditions![Condition::eq("enabled", true)]),
+            op_ongoing("idle"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["idle"]);
+
+    app.behavior_entity().set_prop("enabled", true);
+
+    app.update();
+    app.assert_ran(["idle"]);
+
+    app.update();
+    app.assert_ran(["a"]);
+}
+
+#[test]
+fn replans_on_timer() {
+    let mut app = App::test((
+        ReplanPolicy::every(Time::<Fixed>::default().timestep() * 3),
+        Select,
+        tasks![
+            (op("a"), conditions![Condition::eq("enabled", true)]),
+            op_ongoing("idle"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["idle"]);
+
+    app.behavior_entity().set_prop("enabled", true);
+
+    app.update();
+    app.assert_ran(["idle"]);
+
+    app.update();
+    app.assert_ran(["a"]);
+}
+
+#[test]
+fn periodic_replans_keep_running_plan() {
+    let mut app = App::test((
+        ReplanPolicy::every_ticks(1),
+        Select,
+        tasks![
+            (
+                Sequence,
+                tasks![
+                    (op("a"), conditions![Condition::eq("enabled", true)]),
+                    op("b")
+                ]
+            ),
+            (Sequence, tasks![op("c"), op("d")]),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["c"]);
+
+    app.update();
+    app.assert_ran(["d"]);
+
+    app.update();
+    app.assert_ran(["c"]);
+}
+
+#[test]
+fn does_not_replan_by_default() {
+    let mut app = App::test((
+        ReplanPolicy::default(),
+        Select,
+        tasks![
+            (op("a"), conditions![Condition::eq("enabled", true)]),
+            op_ongoing("idle"),
+        ],
+    ));
+    app.behavior_entity().set_prop("enabled", true);
+    for _ in 0..5 {
+        app.update();
+        app.assert_ran(["idle"]);
+    }
+}
+
+#[test]
+fn replans_on_prop_change() {
+    let mut app = App::test((
+        ReplanPolicy::OnPropChange,
+        Select,
+        tasks![
+            (op("a"), conditions![Condition::eq("enabled", true)]),
+            op_ongoing("idle"),
+        ],
+    ));
+    assert!(app.behavior_entity().contains::<WatchProps>());
+    app.update();
+    app.assert_ran(["idle"]);
+
+    app.behavior_entity().set_prop("enabled", true);
+
+    app.update();
+    app.assert_ran(["a"]);
+}
+
+#[test]
+fn removes_inserted_watch_props_with_policy() {
+    let mut app = App::test((ReplanPolicy::OnPropChange, op_ongoing("idle")));
+    app.behavior_entity().insert(ReplanPolicy::OnPropChange);
+    app.world_mut().flush();
+    assert!(app.behavior_entity().contains::<WatchProps>());
+
+    app.behavior_entity().insert(ReplanPolicy::OnFailure);
+    app.world_mut().flush();
+    assert!(!app.behavior_entity().contains::<WatchProps>());
+}
+
+#[test]
+fn keeps_own_watch_props_with_policy() {
+    let watch = WatchProps::new(["enabled"]);
+    let mut app = App::test((
+        watch.clone(),
+        ReplanPolicy::OnPropChange,
+        op_ongoing("idle"),
+    ));
+    assert_eq!(app.behavior_entity().get::<WatchProps>(), Some(&watch));
+
+    app.behavior_entity().remove::<ReplanPolicy>();
+    app.world_mut().flush();
+    assert_eq!(app.behavior_entity().get::<WatchProps>(), Some(&watch));
+}
+
+#[test]
+fn runs_without_time() {
+    let mut app = App::new();
+    app.add_plugins(BaePlugin::new(Update))
+        .init_resource::<Ran>();
+    app.world_mut().spawn((Plan::new(), op("a")));
+    app.update();
+    app.update();
+    assert!(app.world().resource::<Ran>().0.contains(&"a".to_string()));
+}
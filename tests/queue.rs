@@ -0,0 +1,306 @@
+//! Tests spreading planning over several runs with a [`PlanningQueue`]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::prelude::*;
+use common::*;
+use core::time::Duration;
+
+#[test]
+fn plans_immediately_without_budget() {
+    let mut app = App::test_queued(PlanningBudget::Unlimited, |commands| {
+        for name in ["a", "b", "c"] {
+            commands.spawn(op(name)).trigger(UpdatePlan::new);
+        }
+    });
+    assert!(app.world().resource::<PlanningQueue>().is_empty());
+
+    app.update();
+    app.assert_ran(["a", "b", "c"]);
+}
+
+#[test]
+fn limits_planners_per_run() {
+    let mut app = App::test_queued(PlanningBudget::Planners(1), |commands| {
+        for name in ["a", "b", "c"] {
+            commands.spawn(op(name)).trigger(UpdatePlan::new);
+        }
+    });
+    assert_eq!(app.world().resource::<PlanningQueue>().len(), 3);
+
+    app.update();
+    app.assert_ran(["a"]);
+
+    app.update();
+    app.assert_ran(["b"]);
+
+    app.update();
+    app.assert_ran(["c"]);
+
+    // a went to the back of the queue after its plan completed
+    app.update();
+    app.assert_ran(["a"]);
+}
+
+#[test]
+fn limits_planning_time_per_run() {
+    let mut app = App::test_queued(PlanningBudget::Time(Duration::ZERO), |commands| {
+        for name in ["a", "b"] {
+            commands.spawn(op(name)).trigger(UpdatePlan::new);
+        }
+    });
+    app.update();
+    app.assert_ran(["a"]);
+
+    app.update();
+    app.assert_ran(["b"]);
+}
+
+#[test]
+fn plans_empty_plans_first() {
+    let mut app = App::test_queued(PlanningBudget::Unlimited, |commands| {
+        commands.spawn(op_ongoing("busy")).trigger(UpdatePlan::new);
+        commands.spawn(op_ongoing("idle")).trigger(UpdatePlan::new);
+    });
+    app.update();
+    app.assert_ran(["busy", "idle"]);
+
+    app.world_mut().resource_mut::<PlanningQueue>().budget = PlanningBudget::Planners(1);
+    let busy = app.find_entity("busy");
+    let idle = app.find_entity("idle");
+    app.world_mut().trigger(UpdatePlan::new(busy));
+    app.world_mut().entity_mut(idle).insert(Plan::new());
+
+    app.update();
+    app.assert_ran(["busy", "idle"]);
+    let queue = app.world().resource::<PlanningQueue>();
+    assert!(queue.contains(busy));
+    assert!(!queue.contains(idle));
+
+    app.update();
+    assert!(app.world().resource::<PlanningQueue>().is_empty());
+}
+
+#[test]
+fn plans_empty_plans_first_in_every_run() {
+    let mut app = App::test_queued(PlanningBudget::Unlimited, |commands| {
+        for name in ["busy", "idle_a", "idle_b"] {
+            commands.spawn(op_ongoing(name)).trigger(UpdatePlan::new);
+        }
+    });
+    app.update();
+    app.assert_ran(["busy", "idle_a", "idle_b"]);
+
+    app.world_mut().resource_mut::<PlanningQueue>().budget = PlanningBudget::Planners(1);
+    let busy = app.find_entity("busy");
+    app.world_mut().trigger(UpdatePlan::new(busy));
+    for name in ["idle_a", "idle_b"] {
+        let idle = app.find_entity(name);
+        app.world_mut().entity_mut(idle).insert(Plan::new());
+    }
+
+    app.update();
+    app.assert_ran(["busy", "idle_a"]);
+    app.update();
+    app.assert_ran(["busy", "idle_a", "idle_b"]);
+    assert!(app.world().resource::<PlanningQueue>().contains(busy));
+
+    app.update();
+    assert!(app.world().resource::<PlanningQueue>().is_empty());
+}
+
+#[test]
+fn does_not_starve_running_plans() {
+    let mut app = App::test_queued(PlanningBudget::Planners(1), |commands| {
+        commands.spawn(op_ongoing("busy")).trigger(UpdatePlan::new);
+        for _ in 0..3 {
+            commands.spawn(unplannable()).trigger(UpdatePlan::new);
+        }
+    });
+    app.update();
+    app.assert_ran(["busy"]);
+
+    // The unplannable agents request a new plan in every run and are planned first,
+    // until busy was passed over for too many runs
+    let busy = app.find_entity("busy");
+    app.world_mut().trigger(UpdatePlan::new(busy));
+    for _ in 0..PlanningQueue::MAX_SKIPPED_RUNS {
+        app.update();
+        assert!(app.world().resource::<PlanningQueue>().contains(busy));
+    }
+    app.update();
+    assert!(!app.world().resource::<PlanningQueue>().contains(busy));
+}
+
+#[test]
+fn does_not_queue_twice() {
+    let mut app = App::test_queued(PlanningBudget::Planners(1), |commands| {
+        for name in ["a", "b"] {
+            commands.spawn(op(name)).trigger(UpdatePlan::new);
+        }
+    });
+    let a = app.find_entity("a");
+    app.world_mut().trigger(UpdatePlan::new(a));
+    assert_eq!(app.world().resource::<PlanningQueue>().len(), 2);
+}
+
+#[test]
+fn plans_in_parallel() {
+    let queue = PlanningQueue::default().with_parallel(true);
+    let mut app = App::test_queue(queue, |commands| {
+        commands
+            .spawn((
+                Plan::new(),
+                Name::new("guard"),
+                Select,
+                tasks![
+                    (task("flee"), conditions![Condition::eq("scared", true)]),
+                    task("attack"),
+                ],
+            ))
+            .trigger(UpdatePlan::new);
+        commands
+            .spawn((
+                Plan::new(),
+                Name::new("worker"),
+                Sequence,
+                Backtrack,
+                tasks![
+                    (task("fetch"), effects![Effect::set("has_tool", true)]),
+                    (task("work"), conditions![Condition::eq("has_tool", true)]),
+                ],
+            ))
+            .trigger(UpdatePlan::new);
+    });
+    assert_eq!(app.world().resource::<PlanningQueue>().len(), 2);
+
+    app.update();
+    app.assert_ran(["attack", "fetch"]);
+    assert!(app.world().resource::<PlanningQueue>().is_empty());
+
+    app.update();
+    app.assert_ran(["attack", "work"]);
+}
+
+#[test]
+fn limits_planners_per_run_in_parallel() {
+    let queue = PlanningQueue::new(PlanningBudget::Planners(1)).with_parallel(true);
+    let mut app = App::test_queue(queue, |commands| {
+        for name in ["a", "b"] {
+            commands.spawn(op(name)).trigger(UpdatePlan::new);
+        }
+    });
+    app.update();
+    app.assert_ran(["a"]);
+
+    app.update();
+    app.assert_ran(["b"]);
+}
+
+#[test]
+fn skips_planners_despawned_in_parallel() {
+    let queue = PlanningQueue::default().with_parallel(true);
+    let mut app = App::test_queue(queue, |commands| {
+        for name in ["a", "b"] {
+            commands.spawn(op(name)).trigger(UpdatePlan::new);
+        }
+    });
+    let b = app.find_entity("b");
+    app.add_observer(
+        move |replace: On<ReplacePlan>, names: Query<&Name>, mut commands: Commands| {
+            if names
+                .get(replace.entity)
+                .is_ok_and(|name| name.as_str() == "a")
+            {
+                commands.entity(b).despawn();
+            }
+        },
+    );
+
+    app.update();
+    app.assert_ran(["a"]);
+    assert!(app.world().get_entity(b).is_err());
+}
+
+#[test]
+fn falls_back_for_system_conditions() {
+    let queue = PlanningQueue::default().with_parallel(true);
+    let mut app = App::test_queue(queue, |commands| {
+        commands
+            .spawn((
+                Plan::new(),
+                Name::new("guard"),
+                Select,
+                tasks![
+                    (
+                        task("flee"),
+                        conditions![SystemCondition::new(|_: In<ConditionInput>| true)]
+                    ),
+                    task("attack"),
+                ],
+            ))
+            .trigger(UpdatePlan::new);
+        commands.spawn(op("worker")).trigger(UpdatePlan::new);
+    });
+    app.update();
+    app.assert_ran(["flee", "worker"]);
+}
+
+trait QueueApp {
+    fn test_queued(
+        budget: PlanningBudget,
+        spawn: impl Fn(&mut Commands) + Send + Sync + 'static,
+    ) -> App;
+    fn test_queue(
+        queue: PlanningQueue,
+        spawn: impl Fn(&mut Commands) + Send + Sync + 'static,
+    ) -> App;
+}
+
+impl QueueApp for App {
+    fn test_queued(
+        budget: PlanningBudget,
+        spawn: impl Fn(&mut Commands) + Send + Sync + 'static,
+    ) -> App {
+        App::test_queue(PlanningQueue::new(budget), spawn)
+    }
+
+    fn test_queue(
+        queue: PlanningQueue,
+        spawn: impl Fn(&mut Commands) + Send + Sync + 'static,
+    ) -> App {
+        let mut app = App::test_empty();
+        app.insert_resource(queue)
+            .add_systems(Startup, move |mut commands: Commands| {
+                spawn(&mut commands);
+            });
+        app.finish();
+        app.update();
+        app.assert_ran([]);
+        app
+    }
+}
+
+// The following functions are not reflective of real user code and are here to make the test suite more simple to set up.
+
+fn op(name: &str) -> impl Bundle {
+    (Plan::new(), common::op(name))
+}
+
+fn op_ongoing(name: &str) -> impl Bundle {
+    (Plan::new(), common::op_ongoing(name))
+}
+
+fn unplannable() -> impl Bundle {
+    (
+        Plan::new(),
+        Name::new("unplannable"),
+        Select,
+        tasks![(task("never"), conditions![Condition::always_false()])],
+    )
+}
+
+fn task(name: &str) -> impl Bundle {
+    common::op(name)
+}
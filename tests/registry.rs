@@ -0,0 +1,138 @@
+//! Tests referring to operators, conditions and effects by name
+
+mod common;
+
+use bevy::{
+    prelude::*,
+    reflect::{FromReflect, ReflectRef},
+};
+use bevy_bae::prelude::*;
+use common::*;
+
+#[test]
+fn runs_named_operators() {
+    let mut app = App::test_registered((
+        Sequence,
+        tasks![
+            (Name::new("a"), NamedOperator::new("a")),
+            (Name::new("b"), NamedOperator::new("b")),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["a"]);
+
+    app.update();
+    app.assert_ran(["b"]);
+}
+
+#[test]
+fn named_operators_share_their_system() {
+    let mut app = App::test_registered((
+        Sequence,
+        tasks![
+            (Name::new("a"), NamedOperator::new("a")),
+            (Name::new("a again"), NamedOperator::new("a")),
+        ],
+    ));
+    let system_ids = app
+        .world_mut()
+        .query::<&Operator>()
+        .iter(app.world())
+        .map(Operator::system_id)
+        .collect::<Vec<_>>();
+    assert_eq!(system_ids.len(), 2);
+    assert_eq!(system_ids[0], system_ids[1]);
+    assert_eq!(
+        Some(system_ids[0]),
+        app.world().resource::<BehaviorRegistry>().operator_id("a")
+    );
+
+    // Despawning one of them does not unregister the shared system
+    let a = app.find_entity("a");
+    app.world_mut().entity_mut(a).despawn();
+    let root = app.behavior_entity().id();
+    app.world_mut().trigger(UpdatePlan::new(root));
+    app.update();
+    app.assert_ran(["a"]);
+}
+
+#[test]
+fn applies_named_conditions_and_effects() {
+    let mut app = App::test_registered((
+        Select,
+        tasks![
+            (
+                Name::new("a"),
+                NamedOperator::new("a"),
+                conditions![NamedCondition::new("is_ready")],
+            ),
+            (
+                Name::new("b"),
+                NamedOperator::new("b"),
+                effects![NamedEffect::new("get_ready")],
+            ),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["b"]);
+    assert!(*app.behavior_entity().get_prop::<bool>("ready"));
+
+    app.update();
+    app.assert_ran(["a"]);
+}
+
+#[test]
+fn named_plan_only_effects_are_not_applied() {
+    let mut app = App::test_registered((
+        Name::new("b"),
+        NamedOperator::new("b"),
+        effects![NamedEffect::new("get_ready").plan_only()],
+    ));
+    app.update();
+    app.assert_ran(["b"]);
+    assert!(!*app.behavior_entity().get_prop::<bool>("ready"));
+}
+
+#[test]
+fn named_components_survive_reflection() {
+    let mut app = App::test_registered((
+        Name::new("b"),
+        NamedOperator::new("b"),
+        effects![NamedEffect::new("get_ready")],
+    ));
+    let entity = app.behavior_entity().id();
+    let operator = app.world().get::<NamedOperator>(entity).unwrap();
+    // Reflected field by field, so scenes can serialize it without any serde implementations
+    assert!(matches!(operator.reflect_ref(), ReflectRef::TupleStruct(_)));
+    let reflected = operator.reflect_clone().unwrap();
+    assert_eq!(
+        NamedOperator::from_reflect(reflected.as_partial_reflect()),
+        Some(NamedOperator::new("b"))
+    );
+
+    let effect = app
+        .world_mut()
+        .query::<&NamedEffect>()
+        .single(app.world())
+        .unwrap();
+    let reflected = effect.reflect_clone().unwrap();
+    assert_eq!(
+        NamedEffect::from_reflect(reflected.as_partial_reflect()),
+        Some(NamedEffect::new("get_ready"))
+    );
+}
+
+trait RegistryApp {
+    fn test_registered(behavior: impl Bundle) -> App;
+}
+
+impl RegistryApp for App {
+    fn test_registered(behavior: impl Bundle) -> App {
+        App::test_with(behavior, |app| {
+            app.register_operator("a", ran("a"))
+                .register_operator("b", ran("b"))
+                .register_condition("is_ready", Condition::eq("ready", true))
+                .register_effect("get_ready", Effect::set("ready", true));
+        })
+    }
+}
@@ -0,0 +1,126 @@
+//! Tests planners sharing a single task hierarchy
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::prelude::*;
+use common::*;
+
+#[test]
+fn plans_with_shared_tasks() {
+    let mut app = App::test_shared();
+    let domain = app.spawn_domain((
+        Select,
+        tasks![
+            (op("attack"), conditions![Condition::eq("angry", true)]),
+            op("idle"),
+        ],
+    ));
+    let alice = app.spawn_agent("alice", domain);
+    let bob = app.spawn_agent("bob", domain);
+    app.world_mut().entity_mut(alice).set_prop("angry", true);
+    app.world_mut().trigger(UpdatePlan::new(alice));
+    app.world_mut().trigger(UpdatePlan::new(bob));
+    app.update();
+    app.assert_ran_sorted(["alice: attack", "bob: idle"]);
+
+    // No tasks were spawned for the agents
+    let world = app.world_mut();
+    assert_eq!(world.query::<&TaskOf>().iter(world).count(), 2);
+    assert!(!world.entity(alice).contains::<Tasks>());
+    assert_eq!(
+        world.entity(domain).get::<DomainUsers>().unwrap().to_vec(),
+        vec![alice, bob]
+    );
+    assert!(!world.entity(domain).contains::<Plan>());
+}
+
+#[test]
+fn keeps_plans_per_agent() {
+    let mut app = App::test_shared();
+    let domain = app.spawn_domain((Sequence, tasks![op("walk"), op("look")]));
+    let alice = app.spawn_agent("alice", domain);
+    app.world_mut().trigger(UpdatePlan::new(alice));
+    app.update();
+    app.assert_ran_sorted(["alice: walk"]);
+
+    let bob = app.spawn_agent("bob", domain);
+    app.world_mut().trigger(UpdatePlan::new(bob));
+    app.update();
+    app.assert_ran_sorted(["alice: look", "bob: walk"]);
+}
+
+#[test]
+fn applies_shared_effects_to_the_agent() {
+    let mut app = App::test_shared();
+    let domain = app.spawn_domain((op("walk"), effects![Effect::set("walked", true)]));
+    let alice = app.spawn_agent("alice", domain);
+    app.world_mut().trigger(UpdatePlan::new(alice));
+    app.update();
+    app.assert_ran_sorted(["alice: walk"]);
+
+    let world = app.world_mut();
+    assert!(*world.entity_mut(alice).get_prop::<bool>("walked"));
+    assert!(!world.entity(domain).contains::<Props>());
+}
+
+#[test]
+fn despawning_domain_removes_users() {
+    let mut app = App::test_shared();
+    let domain = app.spawn_domain(op("walk"));
+    let alice = app.spawn_agent("alice", domain);
+    app.world_mut().entity_mut(domain).despawn();
+    assert!(!app.world().entity(alice).contains::<UsesDomain>());
+}
+
+trait SharedApp {
+    fn test_shared() -> App;
+    fn spawn_domain(&mut self, behavior: impl Bundle) -> Entity;
+    fn spawn_agent(&mut self, name: &'static str, domain: Entity) -> Entity;
+    /// Like [`TestApp::assert_ran`], but ignores the order in which the agents ran.
+    #[track_caller]
+    fn assert_ran_sorted<const N: usize>(&self, names: [&'static str; N]);
+}
+
+impl SharedApp for App {
+    fn test_shared() -> App {
+        let mut app = App::test_empty();
+        app.finish();
+        app.update();
+        app.assert_ran([]);
+        app
+    }
+
+    fn spawn_domain(&mut self, behavior: impl Bundle) -> Entity {
+        self.world_mut().spawn(behavior).id()
+    }
+
+    fn spawn_agent(&mut self, name: &'static str, domain: Entity) -> Entity {
+        self.world_mut()
+            .spawn((Name::new(name), Plan::new(), UsesDomain(domain)))
+            .id()
+    }
+
+    #[track_caller]
+    fn assert_ran_sorted<const N: usize>(&self, expected: [&'static str; N]) {
+        let expected = expected.map(ToString::to_string).to_vec();
+        let mut actual = self.world().resource::<Ran>().0.clone();
+        actual.sort();
+        assert_eq!(expected, actual);
+    }
+}
+
+// The following functions are not reflective of real user code and are here to make the test suite more simple to set up.
+
+fn op(name: &'static str) -> Operator {
+    Operator::new(
+        move |input: In<OperatorInput>,
+              mut ran: ResMut<Ran>,
+              names: Query<&Name>|
+              -> OperatorStatus {
+            let agent = names.get(input.entity).unwrap();
+            ran.0.push(format!("{agent}: {name}"));
+            OperatorStatus::Success
+        },
+    )
+}
@@ -0,0 +1,87 @@
+//! Tests simulating plans with [`SimulatePlanExt`]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::prelude::*;
+use common::*;
+
+#[test]
+fn simulates_with_hypothetical_props() {
+    let mut app = App::test((
+        Plan::new(),
+        Select,
+        tasks![
+            (op("flee"), conditions![Condition::eq("scared", true)]),
+            op_ongoing("attack"),
+        ],
+    ));
+    app.update();
+    app.assert_ran(["attack"]);
+
+    let planner = app.behavior_entity().id();
+    let live_plan = app.behavior_entity().get::<Plan>().unwrap().clone();
+    let mut props = Props::new();
+    props.set("scared", true);
+    let simulated = app.world_mut().simulate_plan(planner, props).unwrap();
+
+    let flee = app.find_entity("flee");
+    assert_eq!(simulated.plan.operators_total, vec![flee]);
+    assert_eq!(simulated.plan.front().unwrap().entity, flee);
+    assert_eq!(simulated.mtr().0, vec![0]);
+
+    // The live entity is untouched
+    assert_eq!(app.behavior_entity().get::<Plan>(), Some(&live_plan));
+    app.update();
+    app.assert_ran(["attack"]);
+}
+
+#[test]
+fn predicts_props_after_plan() {
+    let mut app = App::test((
+        Plan::new(),
+        Sequence,
+        tasks![
+            (op("a"), effects![Effect::set("a_done", true)]),
+            (op("b"), effects![Effect::toggle("b_done")]),
+        ],
+        effects![Effect::set("done", true)],
+    ));
+    let planner = app.behavior_entity().id();
+    let mut simulated = app
+        .world_mut()
+        .simulate_plan(planner, Props::new())
+        .unwrap();
+
+    assert_eq!(simulated.plan.len(), 2);
+    assert!(*simulated.props.get_mut::<bool>("a_done"));
+    assert!(*simulated.props.get_mut::<bool>("b_done"));
+    assert!(*simulated.props.get_mut::<bool>("done"));
+    assert!(!*app.behavior_entity().get_prop::<bool>("a_done"));
+}
+
+#[test]
+fn simulates_failed_plan() {
+    let mut app = App::test((
+        Plan::new(),
+        Select,
+        tasks![(op("a"), conditions![Condition::eq("enabled", true)])],
+    ));
+    let planner = app.behavior_entity().id();
+    let simulated = app
+        .world_mut()
+        .simulate_plan(planner, Props::new())
+        .unwrap();
+    assert!(simulated.is_empty());
+}
+
+#[test]
+fn errors_without_tasks() {
+    let mut app = App::test(Plan::new());
+    let planner = app.behavior_entity().id();
+    assert!(
+        app.world_mut()
+            .simulate_plan(planner, Props::new())
+            .is_err()
+    );
+}
@@ -0,0 +1,74 @@
+//! Tests effects backed by systems
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::prelude::*;
+use common::*;
+
+#[test]
+fn runs_system_effect_after_projection() {
+    let mut app = App::test((
+        Sequence,
+        tasks![
+            (
+                op("draw"),
+                effects![(Effect::set("armed", true), SystemEffect::new(equip))],
+            ),
+            (op("attack"), conditions![Condition::eq("armed", true)]),
+        ],
+    ));
+    assert!(!app.behavior_entity().contains::<Weapon>());
+
+    app.update();
+    app.assert_ran(["draw"]);
+    assert!(app.behavior_entity().contains::<Weapon>());
+    assert!(*app.behavior_entity().get_prop::<bool>("armed"));
+
+    app.update();
+    app.assert_ran(["attack"]);
+}
+
+#[test]
+fn runs_system_effect_without_projection() {
+    let mut app = App::test((op("draw"), effects![SystemEffect::new(equip)]));
+    app.update();
+    app.assert_ran(["draw"]);
+    assert!(app.behavior_entity().contains::<Weapon>());
+}
+
+#[test]
+fn skips_plan_only_system_effect() {
+    let mut app = App::test((
+        op("draw"),
+        effects![(
+            Effect::set("armed", true).plan_only(),
+            SystemEffect::new(equip)
+        )],
+    ));
+    app.update();
+    app.assert_ran(["draw"]);
+    assert!(!app.behavior_entity().contains::<Weapon>());
+    assert!(!*app.behavior_entity().get_prop::<bool>("armed"));
+}
+
+#[test]
+fn does_not_run_system_effect_on_failure() {
+    let mut app = App::test((
+        Name::new("fail"),
+        Operator::new(|_: In<OperatorInput>| OperatorStatus::Failure),
+        effects![SystemEffect::new(equip)],
+    ));
+    app.update();
+    assert!(!app.behavior_entity().contains::<Weapon>());
+}
+
+// The following functions are not reflective of real user code and are here to make the test suite more simple to set up.
+
+#[derive(Component)]
+struct Weapon;
+
+fn equip(input: In<EffectInput>, mut commands: Commands, effects: Query<(), With<EffectOf>>) {
+    assert!(effects.contains(input.effect));
+    commands.entity(input.entity).insert(Weapon);
+}
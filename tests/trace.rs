@@ -0,0 +1,122 @@
+//! Tests recording a [`DecompositionTrace`]
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::{plan::mtr::Mtr, prelude::*};
+use common::*;
+
+#[test]
+fn records_visited_tasks_and_conditions() {
+    let mut app = App::test((
+        Plan::new(),
+        DecompositionTrace::default(),
+        Select,
+        tasks![
+            (op("flee"), conditions![Condition::eq("scared", true)]),
+            op_ongoing("attack"),
+        ],
+    ));
+    let trace = app.trace();
+    assert_eq!(trace.outcome, TraceOutcome::Success);
+    assert_eq!(trace.subtasks.len(), 2);
+
+    let flee = &trace.subtasks[0];
+    assert_eq!(flee.name, Some(Name::new("flee")));
+    assert_eq!(flee.outcome, TraceOutcome::Failure);
+    assert_eq!(flee.conditions.len(), 1);
+    assert!(!flee.conditions[0].fulfilled);
+    assert_eq!(
+        flee.conditions[0].expr,
+        Some(Condition::eq("scared", true).to_string())
+    );
+    assert_eq!(flee.conditions[0].props.len(), 1);
+    assert_eq!(flee.conditions[0].props[0].0, Ustr::from("scared"));
+
+    let attack = &trace.subtasks[1];
+    assert_eq!(attack.name, Some(Name::new("attack")));
+    assert_eq!(attack.outcome, TraceOutcome::Success);
+    assert!(attack.conditions.is_empty());
+}
+
+#[test]
+fn records_rejections() {
+    let mut app = App::test((
+        Plan::new(),
+        DecompositionTrace::default(),
+        Select,
+        tasks![
+            (op_ongoing("a"), conditions![Condition::eq("enabled", true)]),
+            op_ongoing("b"),
+        ],
+    ));
+    app.behavior_entity().set_prop("enabled", true);
+    app.replan();
+    assert_eq!(app.trace().outcome, TraceOutcome::Success);
+
+    app.behavior_entity().set_prop("enabled", false);
+    app.replan();
+    let trace = app.trace();
+    assert_eq!(trace.outcome, TraceOutcome::Aborted);
+    assert_eq!(trace.subtasks[0].outcome, TraceOutcome::Failure);
+    assert_eq!(
+        trace.subtasks[1].outcome,
+        TraceOutcome::Rejected {
+            mtr: Mtr(vec![1]),
+            previous_mtr: Mtr(vec![0]),
+        }
+    );
+}
+
+#[test]
+fn renders_tree() {
+    let mut app = App::test((
+        Plan::new(),
+        DecompositionTrace::default(),
+        Select,
+        tasks![
+            (
+                Sequence,
+                tasks![(op("flee"), conditions![Condition::eq("scared", true)])],
+            ),
+            op_ongoing("attack"),
+        ],
+    ));
+    let rendered = app
+        .behavior_entity()
+        .get::<DecompositionTrace>()
+        .unwrap()
+        .to_string();
+    let lines = rendered.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "decomposition trace:");
+    assert!(lines[1].starts_with("- ") && lines[1].ends_with("(root): success"));
+    assert!(lines[2].starts_with("  - ") && lines[2].ends_with(": failure"));
+    assert!(lines[3].starts_with("    - ") && lines[3].ends_with("(flee): failure"));
+    assert!(
+        lines[4].starts_with("      - condition ")
+            && lines[4].contains(&format!("{} is false", Condition::eq("scared", true)))
+    );
+    assert!(lines[5].starts_with("  - ") && lines[5].ends_with("(attack): success"));
+}
+
+trait TraceApp {
+    fn trace(&mut self) -> TracedTask;
+    fn replan(&mut self);
+}
+
+impl TraceApp for App {
+    fn trace(&mut self) -> TracedTask {
+        self.behavior_entity()
+            .get::<DecompositionTrace>()
+            .unwrap()
+            .root
+            .clone()
+            .unwrap()
+    }
+
+    fn replan(&mut self) {
+        let entity = self.behavior_entity().id();
+        self.world_mut().trigger(UpdatePlan::new(entity));
+        self.world_mut().flush();
+    }
+}
@@ -0,0 +1,187 @@
+//! Tests replanning automatically when watched props change
+
+mod common;
+
+use bevy::prelude::*;
+use bevy_bae::prelude::*;
+use common::*;
+
+#[test]
+fn replans_on_watched_condition_prop() {
+    let mut app = App::test_watched((
+        WatchProps::conditions(),
+        Select,
+        tasks![
+            (
+                Sequence,
+                tasks![
+                    (op("a"), conditions![Condition::eq("enabled", true)]),
+                    op("b")
+                ]
+            ),
+            (Sequence, tasks![op("c"), op("d")])
+        ],
+    ));
+    app.update();
+    app.assert_ran(["c"]);
+
+    app.behavior_entity().set_prop("enabled", true);
+
+    app.update();
+    app.assert_ran(["a"]);
+
+    app.update();
+    app.assert_ran(["b"]);
+}
+
+#[test]
+fn replans_on_explicitly_watched_prop() {
+    let mut app = App::test_watched((
+        WatchProps::new(["enabled"]),
+        Select,
+        tasks![
+            (
+                op("a"),
+                conditions![Condition::new(|props| *props.get_mut::<bool>("enabled"))]
+            ),
+            (Sequence, tasks![op("c"), op("d")])
+        ],
+    ));
+    app.update();
+    app.assert_ran(["c"]);
+
+    app.behavior_entity().set_prop("enabled", true);
+
+    app.update();
+    app.assert_ran(["a"]);
+}
+
+#[test]
+fn does_not_replan_on_unwatched_prop() {
+    let mut app = App::test_watched((
+        WatchProps::new(["unrelated"]),
+        Select,
+        tasks![
+            (
+                Sequence,
+                tasks![
+                    (op("a"), conditions![Condition::eq("enabled", true)]),
+                    op("b")
+                ]
+            ),
+            (Sequence, tasks![op("c"), op("d")])
+        ],
+    ));
+    app.update();
+    app.assert_ran(["c"]);
+
+    app.behavior_entity().set_prop("enabled", true);
+
+    app.update();
+    app.assert_ran(["d"]);
+}
+
+#[test]
+fn does_not_replan_on_internal_prop_change() {
+    let mut app = App::test_watched((
+        WatchProps::conditions(),
+        Select,
+        tasks![
+            (
+                Sequence,
+                tasks![
+                    (op("a"), conditions![Condition::eq("enabled", true)]),
+                    op("b")
+                ]
+            ),
+            (
+                Sequence,
+                tasks![(op("c"), effects![Effect::set("enabled", true)]), op("d")]
+            )
+        ],
+    ));
+    app.update();
+    app.assert_ran(["c"]);
+
+    app.update();
+    app.assert_ran(["d"]);
+
+    app.update();
+    app.assert_ran(["a"]);
+}
+
+#[test]
+fn replans_on_prop_change_by_operator() {
+    let mut app = App::test_watched((
+        WatchProps::conditions(),
+        Select,
+        tasks![
+            (op("a"), conditions![Condition::eq("enabled", true)]),
+            (
+                Sequence,
+                tasks![
+                    (
+                        Name::new("c"),
+                        Operator::new(
+                            |input: In<OperatorInput>,
+                             mut props: Query<&mut Props>,
+                             mut ran: ResMut<Ran>|
+                             -> OperatorStatus {
+                                props.get_mut(input.entity).unwrap().set("enabled", true);
+                                ran.0.push("c".to_string());
+                                OperatorStatus::Success
+                            }
+                        )
+                    ),
+                    op("d")
+                ]
+            )
+        ],
+    ));
+    app.update();
+    app.assert_ran(["c"]);
+
+    // Unlike effects, operators changing props directly is not anticipated by the plan
+    app.update();
+    app.assert_ran(["a"]);
+}
+
+#[test]
+fn debounces_replans() {
+    let mut app = App::test_watched((
+        WatchProps::new(["flag"]).with_debounce(3),
+        Name::new("ongoing"),
+        Operator::new(|_: In<OperatorInput>| OperatorStatus::Ongoing),
+    ));
+    app.update();
+    app.world_mut().resource_mut::<Replans>().0 = 0;
+
+    let mut replans = Vec::new();
+    for i in 0..6 {
+        app.behavior_entity().set_prop("flag", i % 2 == 0);
+        app.update();
+        replans.push(app.world().resource::<Replans>().0);
+    }
+    assert_eq!(replans, [1, 1, 1, 2, 2, 2]);
+}
+
+trait WatchApp {
+    fn test_watched(behavior: impl Bundle) -> App;
+}
+
+impl WatchApp for App {
+    fn test_watched(behavior: impl Bundle) -> App {
+        App::test_with(behavior, |app| {
+            app.init_resource::<Replans>().add_observer(
+                |_: On<UpdatePlan>, mut replans: ResMut<Replans>| {
+                    replans.0 += 1;
+                },
+            );
+        })
+    }
+}
+
+// The following functions are not reflective of real user code and are here to make the test suite more simple to set up.
+
+#[derive(Resource, Default)]
+struct Replans(usize);